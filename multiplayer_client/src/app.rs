@@ -1,7 +1,18 @@
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+    thread,
+};
+
 use multiplayer_server::{ClientEvent, ServerEvent, ServerGameState};
 use tictactoe_library::{
-    game::{Game, GameState},
-    update::{Action, Move, Opponent, Score},
+    error::GameError,
+    game::{Board, BoardConfig, Game, GameState, Player},
+    numerical::{NumericalGame, NumericalState},
+    player,
+    qubic::{QubicGame, QubicState},
+    record::cell_name,
+    update::{Action, Difficulty, GameCell, Move, Opponent, Score},
 };
 
 pub enum OnlineState {
@@ -34,6 +45,8 @@ use crate::client::Client;
 pub enum AppState {
     Menu(Menu, u8),
     Playing(GameState),
+    PlayingNumerical(NumericalState),
+    PlayingQubic(QubicState),
     Online(ClientWithState),
     Quit,
 }
@@ -43,6 +56,8 @@ impl Clone for AppState {
         match self {
             AppState::Menu(menu, u) => AppState::Menu(menu.clone(), *u),
             AppState::Playing(game_state) => AppState::Playing(game_state.clone()),
+            AppState::PlayingNumerical(state) => AppState::PlayingNumerical(state.clone()),
+            AppState::PlayingQubic(state) => AppState::PlayingQubic(state.clone()),
             AppState::Online(_) => panic!("Cannot clone client"),
             AppState::Quit => AppState::Quit,
             // AppState::PlayingOnline(server_game_state) => AppState::PlayingOnline(server_game_state.clone()),
@@ -56,6 +71,8 @@ impl PartialEq for AppState {
         match (self, other) {
             (AppState::Menu(menu, u), AppState::Menu(menu2, u2)) => menu == menu2 && u == u2,
             (AppState::Playing(game_state), AppState::Playing(game_state2)) => game_state == game_state2,
+            (AppState::PlayingNumerical(state), AppState::PlayingNumerical(state2)) => state == state2,
+            (AppState::PlayingQubic(state), AppState::PlayingQubic(state2)) => state == state2,
             (AppState::Online(_), AppState::Online(_)) => true,
             (AppState::Quit, AppState::Quit) => true,
             // (AppState::PlayingOnline(server_game_state), AppState::PlayingOnline(server_game_state2)) => {
@@ -73,13 +90,62 @@ pub enum Menu {
     Game,
 }
 
+/// The rules a local game is played by, switched in the start menu.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Rules {
+    Classic,
+    /// Whoever completes a line loses
+    Misere,
+    /// Numbers instead of pieces, and a line summing to 15 wins
+    Numerical,
+    /// Four in a row on a 4x4x4 cube
+    Qubic,
+}
+
+impl Rules {
+    /// The rules the start menu switches to from these.
+    pub fn next(self) -> Rules {
+        match self {
+            Rules::Classic => Rules::Misere,
+            Rules::Misere => Rules::Numerical,
+            Rules::Numerical => Rules::Qubic,
+            Rules::Qubic => Rules::Classic,
+        }
+    }
+
+    /// The board a [`Game`] under these rules is played on, here or on the server. Numerical
+    /// tic-tac-toe and Qubic have games of their own, so they leave the classic board.
+    pub fn config(self) -> BoardConfig {
+        BoardConfig {
+            misere: self == Rules::Misere,
+            ..BoardConfig::default()
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rules::Classic => write!(f, "Classic"),
+            Rules::Misere => write!(f, "Misère"),
+            Rules::Numerical => write!(f, "Numerical"),
+            Rules::Qubic => write!(f, "Qubic"),
+        }
+    }
+}
+
 #[derive(PartialEq, Clone)]
 pub struct App {
     game: Game,
+    numerical: NumericalGame,
+    qubic: QubicGame,
+    pub rules: Rules,
     pub score: Score,
     pub state: AppState,
     pub warning_message: Option<String>,
-    pub prev_state: Option<GameState>,
+    pub prev_state: Option<AppState>,
+    /// The board a hint is being worked out for, and where the hint will be left when it is ready
+    hint: Option<(Board, Arc<OnceLock<String>>)>,
 }
 
 impl App {
@@ -87,10 +153,14 @@ impl App {
         let game = Game::new(Opponent::Human);
         App {
             game,
+            numerical: NumericalGame::new(Opponent::Human).unwrap(),
+            qubic: QubicGame::new(Opponent::Human).unwrap(),
+            rules: Rules::Classic,
             score: Score::default(),
             state: AppState::Menu(Menu::Start, 0),
             warning_message: None,
             prev_state: None,
+            hint: None,
         }
     }
 
@@ -103,12 +173,12 @@ impl App {
             AppState::Menu(menu, _) => {
                 match menu {
                     Menu::Start => return Err("Cannot go back from game menu".to_string()),
-                    Menu::Game => self.state = AppState::Playing(self.prev_state.clone().unwrap()),
+                    Menu::Game => self.state = self.prev_state.clone().unwrap(),
                 }
                 Ok(())
             }
-            AppState::Playing(state) => {
-                self.prev_state = Some(state.clone());
+            AppState::Playing(_) | AppState::PlayingNumerical(_) | AppState::PlayingQubic(_) => {
+                self.prev_state = Some(self.state.clone());
                 self.state = AppState::Menu(Menu::Game, 0);
                 Ok(())
             }
@@ -118,21 +188,31 @@ impl App {
 
     fn next_row_menu(&mut self, up: bool) {
         match self.state {
-            AppState::Menu(_, ref mut row) => {
+            AppState::Menu(ref menu, ref mut row) => {
+                let rows = match menu {
+                    Menu::Start => 7,
+                    Menu::Game => 3,
+                };
                 if up {
-                    *row = (*row + 1) % 3;
+                    *row = (*row + 1) % rows;
                 } else {
-                    *row = (*row + 2) % 3;
+                    *row = (*row + rows - 1) % rows;
                 }
             }
             _ => self.warning_message = Some("Cannot change menu row in this state".to_string()),
         }
     }
     fn start_game(&mut self, opponent: Opponent) {
-        self.game = Game::new(opponent);
+        self.game = Game::with_config(opponent, self.rules.config()).unwrap();
         if let Opponent::Online = opponent {
             self.state = AppState::Online(ClientWithState::new("test".to_string()));
             // TODO: Add client name
+        } else if self.rules == Rules::Numerical {
+            self.numerical = NumericalGame::new(opponent).unwrap();
+            self.state = AppState::PlayingNumerical(self.numerical.get_state().unwrap());
+        } else if self.rules == Rules::Qubic {
+            self.qubic = QubicGame::new(opponent).unwrap();
+            self.state = AppState::PlayingQubic(self.qubic.get_state().unwrap());
         } else {
             self.state = AppState::Playing(self.game.get_state().unwrap());
         }
@@ -158,7 +238,10 @@ impl App {
     pub async fn join_session(&mut self, session: String) -> Result<(), String> {
         if let AppState::Online(client_with_state) = &mut self.state {
             if let OnlineState::Menu = client_with_state.state {
-                let msg = client_with_state.client.send(ClientEvent::JoinSession(session)).await?;
+                let msg = client_with_state
+                    .client
+                    .send(ClientEvent::JoinSession(session, self.rules.config()))
+                    .await?;
                 if let ServerEvent::GameStart(game_state) = msg {
                     client_with_state.change_state(OnlineState::Playing(game_state));
                     return Ok(());
@@ -175,7 +258,10 @@ impl App {
 
     pub async fn create_session(&mut self) -> Result<(), String> {
         if let AppState::Online(client) = &mut self.state {
-            let msg = client.client.send(ClientEvent::CreateSession).await?;
+            let msg = client
+                .client
+                .send(ClientEvent::CreateSession(self.rules.config()))
+                .await?;
             if let ServerEvent::Queue(session_id) = msg {
                 client.change_state(OnlineState::Queue(session_id));
                 return Ok(());
@@ -199,47 +285,47 @@ impl App {
                             }
                             self.state = AppState::Playing(state);
                         }
-                        Err(message) => self.warning_message = Some(message),
+                        Err(error) => self.warning_message = Some(error.to_string()),
+                    },
+                    GameState::GameOver(..) => self.warning_message = Some(GameError::GameOver.to_string()),
+                },
+                AppState::PlayingNumerical(state) => match state {
+                    NumericalState::GameInProgress(..) => match self.numerical.update(mv) {
+                        Ok(state) => {
+                            if let NumericalState::GameOver(winner, _) = state {
+                                self.score += win_score(winner);
+                            }
+                            self.state = AppState::PlayingNumerical(state);
+                        }
+                        Err(error) => self.warning_message = Some(error.to_string()),
                     },
-                    GameState::GameOver(..) => self.warning_message = Some("Game is over".to_string()),
+                    NumericalState::GameOver(..) => self.warning_message = Some(GameError::GameOver.to_string()),
                 },
-                AppState::Menu(menu, row) => match mv {
+                AppState::PlayingQubic(state) => match state {
+                    QubicState::GameInProgress(..) => match self.qubic.update(mv) {
+                        Ok(state) => {
+                            if let QubicState::GameOver(winner, _) = state {
+                                self.score += win_score(winner);
+                            }
+                            self.state = AppState::PlayingQubic(state);
+                        }
+                        Err(error) => self.warning_message = Some(error.to_string()),
+                    },
+                    QubicState::GameOver(..) => self.warning_message = Some(GameError::GameOver.to_string()),
+                },
+                AppState::Menu(..) => match mv {
                     Move::Down => self.next_row_menu(true),
                     Move::Up => self.next_row_menu(false),
-                    Move::Place => match menu {
-                        Menu::Start => match row {
-                            0 => self.start_game(Opponent::Human),
-                            1 => self.start_game(Opponent::Random),
-                            2 => self.start_game(Opponent::Minimax),
-                            3 => self.start_game(Opponent::Online),
-                            _ => self.state = AppState::Menu(Menu::Start, 0),
-                        },
-                        Menu::Game => match row {
-                            0 => self.toggle_menu().unwrap(),
-                            1 => self.reset(),
-                            2 => self.quit(),
-                            _ => self.state = AppState::Menu(Menu::Game, 0),
-                        },
-                    },
+                    Move::Place => self.choose_row(),
                     _ => self.warning_message = Some("Cannot move in this state".to_string()),
                 },
                 _ => self.warning_message = Some("Cannot move in this state".to_string()),
             },
             Action::Select(row) => match &self.state {
-                AppState::Menu(menu, _) => match menu {
-                    Menu::Start => self.start_game(match row {
-                        0 => Opponent::Human,
-                        1 => Opponent::Random,
-                        2 => Opponent::Minimax,
-                        3 => Opponent::Online,
-                        _ => Opponent::Human,
-                    }),
-                    Menu::Game => match row {
-                        0 => self.quit(),
-                        1 => self.toggle_menu().unwrap(),
-                        _ => self.warning_message = Some("Invalid menu row".to_string()),
-                    },
-                },
+                AppState::Menu(menu, _) => {
+                    self.state = AppState::Menu(menu.clone(), row);
+                    self.choose_row();
+                }
                 _ => self.warning_message = Some("Cannot select in this state".to_string()),
             },
             Action::Quit => {
@@ -251,15 +337,138 @@ impl App {
             Action::ToggleMenu => {
                 self.toggle_menu().unwrap();
             }
+            Action::Undo => self.take_back(true),
+            Action::Redo => self.take_back(false),
+            Action::Hint => self.hint(),
             // Action::Input(text) => {
 
             // }
         }
     }
 
+    /// Does what the highlighted row of the menu says, whether it was picked with Enter or selected
+    /// directly.
+    fn choose_row(&mut self) {
+        let (menu, row) = match &self.state {
+            AppState::Menu(menu, row) => (menu.clone(), *row),
+            _ => return,
+        };
+        match menu {
+            Menu::Start => match row {
+                0 => self.start_game(Opponent::Human),
+                1 => self.start_game(Opponent::Random),
+                2 => self.start_game(Opponent::Minimax),
+                3 => self.start_game(Opponent::Computer(Difficulty::Easy)),
+                4 => self.start_game(Opponent::Computer(Difficulty::Medium)),
+                5 => self.start_game(Opponent::Computer(Difficulty::Hard)),
+                6 => self.rules = self.rules.next(),
+                7 => self.start_game(Opponent::Online),
+                _ => self.state = AppState::Menu(Menu::Start, 0),
+            },
+            Menu::Game => match row {
+                0 => self.toggle_menu().unwrap(),
+                1 => self.reset(),
+                2 => self.quit(),
+                _ => self.state = AppState::Menu(Menu::Game, 0),
+            },
+        }
+    }
+
+    /// Undoes or redoes a move, keeping the score in line with the result on the board.
+    fn take_back(&mut self, undo: bool) {
+        if let AppState::Playing(_) = self.state {
+            let score = self.game.get_score();
+            let result = if undo { self.game.undo() } else { self.game.redo() };
+            match result {
+                Ok(()) => {
+                    self.score -= score;
+                    self.score += self.game.get_score();
+                    self.state = AppState::Playing(self.game.get_state().unwrap());
+                }
+                Err(error) => self.warning_message = Some(error.to_string()),
+            }
+        } else {
+            self.warning_message = Some("Cannot undo in this state".to_string());
+        }
+    }
+
+    /// Starts working out the best move for the player to move on another thread, so that a slow
+    /// search does not hold up the keys. [`App::poll_hint`] shows it once it is ready.
+    fn hint(&mut self) {
+        if !matches!(self.state, AppState::Playing(GameState::GameInProgress(..))) {
+            self.warning_message = Some("Cannot give a hint in this state".to_string());
+            return;
+        }
+        let cell = match self.game.current_player {
+            Player::Player1 => GameCell::Cross,
+            Player::Player2 => GameCell::Circle,
+        };
+        let board = self.game.board.clone();
+        let message = Arc::new(OnceLock::new());
+        self.hint = Some((board.clone(), message.clone()));
+        self.warning_message = Some("Looking for a hint...".to_string());
+        thread::spawn(move || message.set(hint_message(&board, cell)));
+    }
+
+    /// Shows the hint asked for, once it is ready, in the warning box. A hint for a board that has
+    /// changed since it was asked for is dropped.
+    pub fn poll_hint(&mut self) {
+        let Some((board, message)) = &self.hint else {
+            return;
+        };
+        if let Some(message) = message.get() {
+            if *board == self.game.board && matches!(self.state, AppState::Playing(_)) {
+                self.warning_message = Some(message.clone());
+            }
+            self.hint = None;
+        }
+    }
+
     fn reset(&mut self) {
-        self.game = Game::new(self.game.opponent);
-        self.state = AppState::Playing(self.game.get_state().unwrap());
+        // The game menu resets the game it was opened from
+        let state = match self.state {
+            AppState::Menu(..) => self.prev_state.as_ref(),
+            ref state => Some(state),
+        };
+        match state {
+            Some(AppState::PlayingNumerical(_)) => {
+                self.numerical = NumericalGame::new(self.numerical.opponent).unwrap();
+                self.state = AppState::PlayingNumerical(self.numerical.get_state().unwrap());
+            }
+            Some(AppState::PlayingQubic(_)) => {
+                self.qubic = QubicGame::new(self.qubic.opponent).unwrap();
+                self.state = AppState::PlayingQubic(self.qubic.get_state().unwrap());
+            }
+            _ => {
+                self.game = Game::with_config(self.game.opponent, self.game.board.config).unwrap();
+                self.state = AppState::Playing(self.game.get_state().unwrap());
+            }
+        }
+    }
+}
+
+/// The best move for `cell` on `board`, and what it leads to.
+fn hint_message(board: &Board, cell: GameCell) -> String {
+    match player::hint(board, cell) {
+        Ok(hint) => {
+            let mut target = cell_name(hint.position);
+            // In wild games and Order and Chaos the hint also says which piece to place
+            if board.config.free_pieces() {
+                let piece = if hint.cell == GameCell::Cross { "X" } else { "O" };
+                target = format!("{} at {}", piece, target);
+            }
+            format!("Hint: play {}, which {}", target, hint.value)
+        }
+        Err(error) => error.to_string(),
+    }
+}
+
+/// A point to whoever won, if anyone did.
+fn win_score(winner: Option<Player>) -> Score {
+    match winner {
+        Some(Player::Player1) => Score { player1: 1, player2: 0 },
+        Some(Player::Player2) => Score { player1: 0, player2: 1 },
+        None => Score::default(),
     }
 }
 
@@ -96,6 +96,7 @@ mod tests {
 
     use super::*;
     use test_log::test;
+    use tictactoe_library::game::BoardConfig;
     use tokio::time::{sleep, Duration};
 
     #[test(tokio::test(flavor = "multi_thread", worker_threads = 2))]
@@ -105,7 +106,10 @@ mod tests {
             let mut client = Client::new("test".to_string());
             assert_eq!(
                 ServerEvent::Queue("New".to_string()),
-                client.send(ClientEvent::JoinSession("New".to_string())).await.unwrap()
+                client
+                    .send(ClientEvent::JoinSession("New".to_string(), BoardConfig::default()))
+                    .await
+                    .unwrap()
             );
             log::info!(" Client 1 joined session");
             assert!(matches!(client.recv().await.unwrap(), ServerEvent::GameStart(..)));
@@ -116,7 +120,10 @@ mod tests {
             sleep(Duration::from_millis(1000)).await;
             let mut client = Client::new("another".to_string());
             assert!(matches!(
-                client.send(ClientEvent::JoinSession("New".to_string())).await.unwrap(),
+                client
+                    .send(ClientEvent::JoinSession("New".to_string(), BoardConfig::default()))
+                    .await
+                    .unwrap(),
                 ServerEvent::GameStart(..)
             ));
             log::info!(" Client 2 joined session");
@@ -5,6 +5,7 @@ use tokio::time::delay_for;
 use nanoid::nanoid;
 use serde_json::from_str;
 use std::{collections::HashMap, time::Duration};
+use tictactoe_library::{error::GameError, game::BoardConfig};
 use warp::{filters::BoxedFilter, Reply};
 use websocket_server::{
     message_client as server_msg_client, server,
@@ -80,12 +81,18 @@ pub async fn handle_event(client_id: String, event: String, clients: SafeClients
                 log::error!("failed to get client from clients");
             }
         }
-        ClientEvent::CreateSession => {
+        ClientEvent::CreateSession(config) => {
             log::info!("request from <{}> to create new session", client_id);
 
+            if let Err(error) = config.validate() {
+                if let Some(client) = clients.write().await.get(&client_id) {
+                    message_client(client, &ServerEvent::GameError(error));
+                }
+                return log::error!("refused to create a session with an invalid board");
+            }
             let session_id = {
                 let sessions = &mut sessions.write().await;
-                match create_session(None, sessions) {
+                match create_session(None, config, sessions) {
                     Ok(id) => id,
                     Err(_) => return log::error!("failed to create session.."),
                 }
@@ -101,7 +108,7 @@ pub async fn handle_event(client_id: String, event: String, clients: SafeClients
                 log::error!("failed to get client {} from clients", client_id);
             }
         }
-        ClientEvent::JoinSession(session_id) => {
+        ClientEvent::JoinSession(session_id, config) => {
             log::info!("request from <{}> to join session {}", client_id, session_id);
 
             // If the Session does not exists then we will create it first
@@ -111,8 +118,15 @@ pub async fn handle_event(client_id: String, event: String, clients: SafeClients
                 sessions.read().await.keys().collect::<Vec<_>>()
             );
             if sessions.read().await.get(&session_id).is_none() {
+                if let Err(error) = config.validate() {
+                    if let Some(client) = clients.write().await.get(&client_id) {
+                        message_client(client, &ServerEvent::GameError(error));
+                    }
+                    return log::error!("refused to create a session with an invalid board");
+                }
                 let mut_sessions = &mut sessions.write().await;
-                create_session(Some(&session_id), mut_sessions).expect("unable to create a session with a given id.");
+                create_session(Some(&session_id), config, mut_sessions)
+                    .expect("unable to create a session with a given id.");
             }
 
             let result = if let Some(client) = clients.write().await.get_mut(&client_id) {
@@ -170,8 +184,10 @@ pub async fn handle_event(client_id: String, event: String, clients: SafeClients
                                     }
                                     data.game = Some(game.clone());
                                 }
-                                Err(e) => message_client(client, &ServerEvent::Error(e)),
+                                Err(e) => message_client(client, &ServerEvent::GameError(e)),
                             }
+                        } else {
+                            message_client(client, &ServerEvent::GameError(GameError::NotYourTurn));
                         }
                     }
                 }
@@ -180,10 +196,10 @@ pub async fn handle_event(client_id: String, event: String, clients: SafeClients
     }
 }
 
-/// Creates a new empty Session
+/// Creates a new empty Session whose game is played on a board of shape `config`
 ///
 /// Takes a predefined ID to generate, or uses a randomly generated String
-pub fn create_session(session_id: Option<&str>, sessions: &mut Sessions) -> Result<String, ()> {
+pub fn create_session(session_id: Option<&str>, config: BoardConfig, sessions: &mut Sessions) -> Result<String, ()> {
     log::info!("creating session..");
     let session = &mut Session {
         client_status: HashMap::new(),
@@ -191,7 +207,7 @@ pub fn create_session(session_id: Option<&str>, sessions: &mut Sessions) -> Resu
             Some(id) => String::from(id),
             None => generate_session_id(SESSION_ID_LENGTH),
         },
-        data: ServerGameState::default(),
+        data: ServerGameState::with_config(config),
     };
 
     log::info!("writing new session {} to global sessions", session.id);
@@ -263,7 +279,8 @@ async fn start_game(client: &mut Client, session: &mut Session) -> StartResult {
                 session.id,
                 session.get_clients_with_active_status(true).len()
             );
-            session.data.game = Some(ServerApp::default());
+            // The board was checked when the session was opened
+            session.data.game = Some(ServerApp::with_config(session.data.config).unwrap());
             session.data.players = Players::Full(
                 player.clone(),
                 PlayerData::new(&client.id),
@@ -384,7 +401,7 @@ mod tests {
             .expect("handshake");
         client
             .send(Message::text(
-                to_string(&ClientEvent::JoinSession("Hello".to_string())).unwrap(),
+                to_string(&ClientEvent::JoinSession("Hello".to_string(), BoardConfig::default())).unwrap(),
             ))
             .await;
 
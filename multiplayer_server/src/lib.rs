@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use tictactoe_library::{
-    game::{Game, GameState},
+    error::GameError,
+    game::{BoardConfig, Game, GameState, Line},
     update::{Move, Opponent, Position, Score},
 };
 // use tokio::sync::mpsc;
@@ -27,9 +28,14 @@ use tictactoe_library::{
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ClientEvent {
     ListSessions,
-    CreateSession,
+    /// Opens a session whose game is played on a board of this shape and rules
+    CreateSession(BoardConfig),
     LeaveSession,
-    JoinSession(String),
+    /// Joins the session with this id, opening it with this board if it does not exist yet. An
+    /// open session keeps the board it was opened with
+    JoinSession(String, BoardConfig),
+    /// A move in the session's game. In wild games `Move::PlaceWith` and `Move::PlaceAtWith`
+    /// carry the piece the player chose
     GameEvent(Move),
 }
 
@@ -53,6 +59,9 @@ pub enum ServerEvent {
     /// Error event from the server
     /// Response for any [`ClientEvent`]
     Error(String),
+    /// The move was rejected by the game rules
+    /// Response for `ClientEvent::GameEvent`
+    GameError(GameError),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -107,14 +116,22 @@ pub struct ServerApp {
 
 impl ServerApp {
     pub fn default() -> Self {
-        let game = Game::new(Opponent::Human);
+        ServerApp::start(Game::new(Opponent::Human))
+    }
+
+    /// A game between two online players on a board of shape `config`, such as a misère board.
+    pub fn with_config(config: BoardConfig) -> Result<Self, GameError> {
+        Ok(ServerApp::start(Game::with_config(Opponent::Human, config)?))
+    }
+
+    fn start(game: Game) -> Self {
         ServerApp {
             game_state: game.clone().get_state().unwrap(),
             game,
             score: Score::default(),
         }
     }
-    pub fn update(&mut self, mv: Move) -> Result<(), String> {
+    pub fn update(&mut self, mv: Move) -> Result<(), GameError> {
         match self.game_state {
             GameState::GameInProgress(..) => match self.game.update(mv) {
                 Ok(state) => {
@@ -126,7 +143,16 @@ impl ServerApp {
                 }
                 Err(e) => Err(e),
             },
-            GameState::GameOver(..) => Err("Game is not in progress".to_string()),
+            GameState::GameOver(..) => Err(GameError::GameOver),
+        }
+    }
+
+    /// The lines that decided a finished game, for clients to highlight. Empty while the game is
+    /// in progress or after a draw.
+    pub fn winning_lines(&self) -> &[Line] {
+        match &self.game_state {
+            GameState::GameOver(_, _, lines) => lines,
+            GameState::GameInProgress(..) => &[],
         }
     }
 }
@@ -135,13 +161,21 @@ impl ServerApp {
 pub struct ServerGameState {
     pub players: Players,
     pub game: Option<ServerApp>,
+    /// The board the session's game is played on, chosen by whoever opened the session
+    #[serde(default)]
+    pub config: BoardConfig,
 }
 
 impl ServerGameState {
     pub fn default() -> Self {
+        ServerGameState::with_config(BoardConfig::default())
+    }
+
+    pub fn with_config(config: BoardConfig) -> Self {
         Self {
             players: Players::Empty,
             game: None,
+            config,
         }
     }
 }
@@ -0,0 +1,276 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    game::{BoardConfig, Cells, State},
+    update::{GameCell, Position},
+};
+
+/// The largest number of cells a [`BitBoard`] can hold.
+pub const MAX_CELLS: usize = 128;
+
+/// One bit per cell in row-major order, so the cell at `(x, y)` is bit `y * width + x`.
+pub type Mask = u128;
+
+/// Precomputed masks for every board of one shape.
+#[derive(Debug)]
+pub struct Layout {
+    pub config: BoardConfig,
+    /// Every run of `win_length` cells that wins the game
+    pub lines: Vec<Mask>,
+    /// For each line, the cells just beyond its two ends, which make it an overline when taken
+    /// by the same side
+    pub ends: Vec<Mask>,
+    /// All cells of the board
+    pub full: Mask,
+    /// The cells of the bottom row
+    pub bottom: Mask,
+}
+
+impl Layout {
+    /// The layout for `config`, or `None` if the board has more than [`MAX_CELLS`] cells.
+    ///
+    /// Layouts are built once per shape and shared afterwards. Each thread also remembers the
+    /// last layout it asked for, so that a game or search sticking to one shape, as almost all do,
+    /// never has to take the lock.
+    pub fn get(config: BoardConfig) -> Option<Arc<Layout>> {
+        static LAYOUTS: OnceLock<Mutex<HashMap<BoardConfig, Arc<Layout>>>> = OnceLock::new();
+        thread_local! {
+            static LAST: RefCell<Option<Arc<Layout>>> = const { RefCell::new(None) };
+        }
+        if config.width * config.height > MAX_CELLS {
+            return None;
+        }
+        LAST.with_borrow_mut(|last| {
+            if let Some(layout) = last.as_ref().filter(|layout| layout.config == config) {
+                return Some(layout.clone());
+            }
+            let mut layouts = LAYOUTS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let layout = layouts
+                .entry(config)
+                .or_insert_with(|| Arc::new(Layout::new(config)))
+                .clone();
+            *last = Some(layout.clone());
+            Some(layout)
+        })
+    }
+
+    fn new(config: BoardConfig) -> Layout {
+        let board = crate::game::Board::new(config);
+        let lines = board.lines();
+        let ends = lines
+            .iter()
+            .map(|line| match line[..] {
+                [first, second, ..] => {
+                    let direction = (
+                        second.x as isize - first.x as isize,
+                        second.y as isize - first.y as isize,
+                    );
+                    [
+                        board.offset(first, direction, -1),
+                        board.offset(*line.last().unwrap(), direction, 1),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .fold(0, |mask, pos| mask | bit(config, pos))
+                }
+                _ => 0,
+            })
+            .collect();
+        let lines = lines
+            .iter()
+            .map(|line| line.iter().fold(0, |mask, pos| mask | bit(config, *pos)))
+            .collect();
+        let cells = config.width * config.height;
+        let full = if cells == MAX_CELLS {
+            Mask::MAX
+        } else {
+            (1 << cells) - 1
+        };
+        let bottom = full & !full.checked_shr(config.width as u32).unwrap_or(0);
+        Layout {
+            config,
+            lines,
+            ends,
+            full,
+            bottom,
+        }
+    }
+
+    pub fn position(&self, index: usize) -> Position {
+        Position {
+            x: index % self.config.width,
+            y: index / self.config.width,
+        }
+    }
+
+    pub fn index(&self, pos: Position) -> usize {
+        pos.y * self.config.width + pos.x
+    }
+}
+
+fn bit(config: BoardConfig, pos: Position) -> Mask {
+    1 << (pos.y * config.width + pos.x)
+}
+
+/// A compact board: one mask for the crosses and one for the circles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitBoard {
+    pub crosses: Mask,
+    pub circles: Mask,
+}
+
+impl BitBoard {
+    /// Packs `cells` laid out as `layout`, or `None` if their dimensions do not match it.
+    pub fn from_cells(cells: &Cells, layout: &Layout) -> Option<BitBoard> {
+        if cells.len() != layout.config.height || cells.iter().any(|row| row.len() != layout.config.width) {
+            return None;
+        }
+        let mut board = BitBoard::default();
+        for (i, cell) in cells.iter().flatten().enumerate() {
+            board.set(i, *cell);
+        }
+        Some(board)
+    }
+
+    pub fn to_cells(&self, layout: &Layout) -> Cells {
+        (0..layout.config.height)
+            .map(|y| {
+                (0..layout.config.width)
+                    .map(|x| self.get(y * layout.config.width + x))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn get(&self, index: usize) -> GameCell {
+        if self.crosses >> index & 1 == 1 {
+            GameCell::Cross
+        } else if self.circles >> index & 1 == 1 {
+            GameCell::Circle
+        } else {
+            GameCell::Empty
+        }
+    }
+
+    pub fn set(&mut self, index: usize, cell: GameCell) {
+        let bit: Mask = 1 << index;
+        self.crosses &= !bit;
+        self.circles &= !bit;
+        match cell {
+            GameCell::Cross => self.crosses |= bit,
+            GameCell::Circle => self.circles |= bit,
+            GameCell::Empty => {}
+        }
+    }
+
+    /// The mask of `cell`'s pieces, or of the empty cells.
+    pub fn pieces(&self, cell: GameCell, layout: &Layout) -> Mask {
+        match cell {
+            GameCell::Cross => self.crosses,
+            GameCell::Circle => self.circles,
+            GameCell::Empty => layout.full & !(self.crosses | self.circles),
+        }
+    }
+
+    /// Indices of the empty cells, lowest first.
+    pub fn empty_cells(&self, layout: &Layout) -> impl Iterator<Item = usize> {
+        bits(self.pieces(GameCell::Empty, layout))
+    }
+
+    /// The mask of the cells a piece can be placed in, the same cells as [`Board::available_moves`].
+    ///
+    /// [`Board::available_moves`]: crate::game::Board::available_moves
+    pub fn moves(&self, layout: &Layout) -> Mask {
+        let empty = self.pieces(GameCell::Empty, layout);
+        if !layout.config.gravity {
+            return empty;
+        }
+        // An empty cell is playable when the cell below it is taken or it is on the bottom row
+        let taken = self.crosses | self.circles;
+        empty & ((taken >> layout.config.width) | layout.bottom)
+    }
+
+    pub fn get_state(&self, layout: &Layout) -> State {
+        for (line, ends) in layout.lines.iter().zip(&layout.ends) {
+            for cell in [GameCell::Cross, GameCell::Circle] {
+                let pieces = self.pieces(cell, layout);
+                if pieces & line == *line && (pieces & ends == 0 || layout.config.overline_wins(cell)) {
+                    return State::Win(layout.config.line_winner(cell));
+                }
+            }
+        }
+        if self.pieces(GameCell::Empty, layout) == 0 {
+            layout.config.full_board_state()
+        } else {
+            State::Empty
+        }
+    }
+}
+
+/// Indices of the set bits of `mask`, lowest first.
+pub fn bits(mut mask: Mask) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Board;
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::new(BoardConfig::new(4, 3, 3).unwrap());
+        board.set_cell_force(Position { x: 3, y: 0 }, GameCell::Cross);
+        board.set_cell_force(Position { x: 1, y: 2 }, GameCell::Circle);
+        let layout = Layout::get(board.config).unwrap();
+        let bits = BitBoard::from_cells(&board.cells, &layout).unwrap();
+        assert_eq!(bits.crosses, 1 << 3);
+        assert_eq!(bits.circles, 1 << 9);
+        assert_eq!(bits.to_cells(&layout), board.cells);
+        assert_eq!(bits.empty_cells(&layout).count(), 10);
+    }
+
+    #[test]
+    fn test_state_matches_board() {
+        let layout = Layout::get(BoardConfig::default()).unwrap();
+        assert_eq!(layout.lines.len(), 8);
+        let mut board = BitBoard::default();
+        for i in [2, 4] {
+            board.set(i, GameCell::Circle);
+        }
+        assert_eq!(board.get_state(&layout), State::Empty);
+        board.set(6, GameCell::Circle);
+        assert_eq!(board.get_state(&layout), State::Win(GameCell::Circle));
+        assert!(Layout::get(BoardConfig::new(15, 15, 5).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_exact_length() {
+        let config = BoardConfig {
+            exact_length: true,
+            ..BoardConfig::new(5, 1, 3).unwrap()
+        };
+        let layout = Layout::get(config).unwrap();
+        let mut board = BitBoard::default();
+        for i in 0..4 {
+            board.set(i, GameCell::Cross);
+        }
+        assert_eq!(board.get_state(&layout), State::Empty);
+        board.set(0, GameCell::Circle);
+        assert_eq!(board.get_state(&layout), State::Win(GameCell::Cross));
+    }
+}
@@ -0,0 +1,283 @@
+//! Drawing the game tree below a position as a [Graphviz](https://graphviz.org) graph.
+//!
+//! [`export`] runs a traced search of the position with the [`Solver`], which searches as it
+//! always does but without its transposition table, so that every position it looks at shows up
+//! in the graph. Each node shows its board, the side to move and the value the search found for
+//! it. A value the search only bounded, because a cutoff stopped it early, is marked `≥` or `≤`,
+//! and the moves a cutoff skipped are collected in a dashed node. Positions whose value rests on
+//! the estimates the search made at its depth limit are drawn as undecided.
+//!
+//! Render the output with, for example, `dot -Tsvg tree.dot -o tree.svg`.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::GameError,
+    game::{Board, Player},
+    record::cell_name,
+    solver::{Bound, Event, Outcome, Solver, Value, DECISIVE, WIN},
+    update::{GameCell, Position},
+};
+
+/// Which part of the tree to draw, and how.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DotOptions {
+    /// How many plies below the position to search, `None` for the whole game. Positions at the
+    /// limit, and those above them whose value depends on them, are drawn as undecided.
+    pub depth: Option<usize>,
+    /// Only draw one of the moves that lead to equivalent positions: rotations or reflections of
+    /// each other, or in wild games the same position with the pieces swapped
+    pub symmetry: bool,
+    /// Prune with alpha-beta; without it every move is searched and every value is exact
+    pub alpha_beta: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> DotOptions {
+        DotOptions {
+            depth: None,
+            symmetry: true,
+            alpha_beta: true,
+        }
+    }
+}
+
+/// The game tree below `board`, with `to_move` to play, as a DOT graph, or
+/// [`GameError::BoardTooLarge`] if the solver cannot search the board.
+pub fn export(board: &Board, to_move: Player, options: DotOptions) -> Result<String, GameError> {
+    let events =
+        Solver::with_depth(options.depth).trace(board, to_move.get_cell(), options.alpha_beta, options.symmetry)?;
+    let mut graph = Graph {
+        nodes: 0,
+        out: String::new(),
+    };
+    graph.out.push_str("digraph tree {\n");
+    graph
+        .out
+        .push_str("    node [shape=box, style=filled, fontname=\"monospace\"];\n");
+    // The nodes on the way down from the root to the one being searched
+    let mut path: Vec<Node> = Vec::new();
+    let mut board = board.clone();
+    for event in events {
+        match event {
+            Event::Enter(mov) => {
+                let id = graph.nodes;
+                graph.nodes += 1;
+                let node = match (mov, path.last()) {
+                    (Some((pos, piece)), Some(parent)) => {
+                        let label = move_label(&board, pos, piece);
+                        writeln!(graph.out, "    n{} -> n{} [label=\"{}\"];", parent.id, id, label).unwrap();
+                        board.set_cell_force(pos, piece);
+                        Node {
+                            id,
+                            to_move: parent.to_move.next(),
+                            ply: parent.ply + 1,
+                            mov: Some(pos),
+                        }
+                    }
+                    _ => Node {
+                        id,
+                        to_move,
+                        ply: 0,
+                        mov: None,
+                    },
+                };
+                path.push(node);
+            }
+            Event::Cutoff(moves) => graph.cutoff(path.last().unwrap().id, &board, &moves),
+            Event::Leave { score, bound, decided } => {
+                let node = path.pop().unwrap();
+                let value = if decided {
+                    let prefix = match bound {
+                        Bound::Exact => "",
+                        Bound::Lower => "≥ ",
+                        Bound::Upper => "≤ ",
+                    };
+                    let value = to_value(score, node.ply);
+                    let color = match value.outcome {
+                        Outcome::Win => "palegreen",
+                        Outcome::Draw => "lightyellow",
+                        Outcome::Loss => "lightpink",
+                    };
+                    (format!("{}{}", prefix, value), color)
+                } else {
+                    ("undecided".to_string(), "lightgrey")
+                };
+                graph.node(node.id, &board, node.to_move, &value.0, value.1);
+                if let Some(pos) = node.mov {
+                    board.set_cell_force(pos, GameCell::Empty);
+                }
+            }
+        }
+    }
+    graph.out.push_str("}\n");
+    Ok(graph.out)
+}
+
+/// A position the search is below, and the move that led to it.
+struct Node {
+    id: usize,
+    to_move: Player,
+    ply: i32,
+    mov: Option<Position>,
+}
+
+struct Graph {
+    nodes: usize,
+    out: String,
+}
+
+impl Graph {
+    /// Writes the node for `board`, labelled with its rows, the side to move and `value`.
+    fn node(&mut self, id: usize, board: &Board, to_move: Player, value: &str, color: &str) {
+        let rows = board
+            .cells
+            .iter()
+            .map(|row| row.iter().map(|cell| piece_char(*cell)).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\\n");
+        writeln!(
+            self.out,
+            "    n{} [label=\"{}\\n{} to move\\n{}\", fillcolor=\"{}\"];",
+            id,
+            rows,
+            piece_char(to_move.get_cell()),
+            value,
+            color
+        )
+        .unwrap();
+    }
+
+    /// Writes a dashed node listing the `moves` a cutoff below `parent` skipped.
+    fn cutoff(&mut self, parent: usize, board: &Board, moves: &[(Position, GameCell)]) {
+        let id = self.nodes;
+        self.nodes += 1;
+        let skipped = moves
+            .iter()
+            .map(|(pos, piece)| move_label(board, *pos, *piece))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            self.out,
+            "    n{} [label=\"cut off\\n{}\", style=dashed];\n    n{} -> n{} [style=dashed];",
+            id, skipped, parent, id
+        )
+        .unwrap();
+    }
+}
+
+/// The name of a move, with the piece placed in wild games and Order and Chaos.
+fn move_label(board: &Board, pos: Position, piece: GameCell) -> String {
+    if !board.config.free_pieces() {
+        return cell_name(pos);
+    }
+    format!("{}{}", piece_char(piece), cell_name(pos))
+}
+
+fn piece_char(cell: GameCell) -> char {
+    match cell {
+        GameCell::Empty => '.',
+        GameCell::Cross => 'X',
+        GameCell::Circle => 'O',
+    }
+}
+
+/// The value of a decided score found `ply` plies below the root, counting plies from the node
+/// itself. A decided score that is not a forced result can only be a draw.
+fn to_value(score: i32, ply: i32) -> Value {
+    let (outcome, plies) = match score {
+        score if score > DECISIVE => (Outcome::Win, WIN - score - ply),
+        score if score < -DECISIVE => (Outcome::Loss, WIN + score - ply),
+        _ => (Outcome::Draw, 0),
+    };
+    Value {
+        outcome,
+        plies: plies as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BoardConfig;
+
+    fn count(dot: &str, pattern: &str) -> usize {
+        dot.lines().filter(|line| line.contains(pattern)).count()
+    }
+
+    #[test]
+    fn test_full_tree() {
+        let board: Board = "XOX/XOO/...".parse().unwrap();
+        let options = DotOptions {
+            symmetry: false,
+            alpha_beta: false,
+            ..DotOptions::default()
+        };
+        let dot = export(&board, Player::Player1, options).unwrap();
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(count(&dot, " -> "), 10);
+        assert_eq!(count(&dot, "fillcolor"), 11);
+        assert_eq!(count(&dot, "cut off"), 0);
+        // X wins at once by completing the left column
+        assert!(dot.contains("n0 [label=\"XOX\\nXOO\\n...\\nX to move\\nwins in 1 ply\""));
+        assert!(dot.contains("n0 -> n1 [label=\"a3\"]"));
+    }
+
+    #[test]
+    fn test_alpha_beta() {
+        let board: Board = "XOX/XOO/...".parse().unwrap();
+        let options = DotOptions {
+            symmetry: false,
+            ..DotOptions::default()
+        };
+        let dot = export(&board, Player::Player1, options).unwrap();
+        // Once X's win is found, O's first reply to each other move shows it is no better
+        assert_eq!(count(&dot, "cut off"), 2);
+        assert_eq!(count(&dot, "[style=dashed]"), 2);
+        assert_eq!(count(&dot, "≥ "), 2);
+        assert!(dot.contains("wins in 1 ply\""));
+        assert_eq!(count(&dot, "fillcolor"), 8);
+        assert_eq!(count(&dot, " -> "), 7 + 2);
+    }
+
+    #[test]
+    fn test_depth_and_symmetry() {
+        let options = DotOptions {
+            depth: Some(1),
+            ..DotOptions::default()
+        };
+        // A corner, an edge and the centre
+        let dot = export(&Board::default(), Player::Player1, options).unwrap();
+        assert_eq!(count(&dot, " -> "), 3);
+        // The root rests on the estimates below it, so it is no draw either
+        assert_eq!(count(&dot, "undecided"), 4);
+        assert!(!dot.contains("draws"));
+
+        // A win found above the depth limit stands
+        let board: Board = "XOX/XOO/...".parse().unwrap();
+        let dot = export(&board, Player::Player1, options).unwrap();
+        assert!(dot.contains("n0 [label=\"XOX\\nXOO\\n...\\nX to move\\nwins in 1 ply\""));
+        assert_eq!(count(&dot, "undecided"), 2);
+
+        let dot = export(
+            &Board::default(),
+            Player::Player1,
+            DotOptions {
+                symmetry: false,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(count(&dot, " -> "), 9);
+
+        let mut board = Board::new(BoardConfig::new(2, 2, 2).unwrap());
+        board.config.wild = true;
+        let dot = export(&board, Player::Player1, options).unwrap();
+        // Either piece in any corner is the same position
+        assert_eq!(count(&dot, " -> "), 1);
+        assert!(dot.contains("[label=\"Xa1\"]"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::{error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::Player,
+    record::GameResult,
+    update::{GameCell, Move, Opponent, Position},
+};
+
+/// Reasons a move or query against the game can be rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameError {
+    /// The cell at this position already holds a piece
+    CellOccupied(Position),
+    /// The position lies outside the board
+    OutOfBounds(Position),
+    /// The cursor is already at the edge of the board in the direction of this move
+    CannotMove(Move),
+    /// The game has already been won or drawn
+    GameOver,
+    /// A player tried to move while it was their opponent's turn
+    NotYourTurn,
+    /// There are no empty cells left to play
+    NoMovesAvailable,
+    /// This opponent does not pick its own moves
+    OpponentCannotMove(Opponent),
+    /// No moves have been played that could be taken back
+    NothingToUndo,
+    /// No moves have been taken back that could be played again
+    NothingToRedo,
+    /// The board has too many cells for the solver
+    BoardTooLarge,
+    /// In Ultimate, the position is outside the small board the move has to be played in
+    WrongBoard(Position),
+    /// Under gravity, this column has no empty cells left
+    ColumnFull(usize),
+    /// The player may not place this piece, which outside wild games is any but their own
+    WrongPiece(GameCell),
+    /// In numerical tic-tac-toe, the number belongs to the other player, has already been
+    /// placed, or is not played with at all. Other games take no numbers
+    WrongNumber(u8),
+    /// On a three-dimensional board, there is no layer with this index
+    NoSuchLayer(usize),
+    /// Under renju rules, the first player may not make an overline, two fours or two open threes
+    Forbidden(Position),
+    /// The win length is zero or longer than any line on the board
+    InvalidWinLength(usize),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::CellOccupied(_) => write!(f, "This cell is already taken!"),
+            GameError::OutOfBounds(_) => write!(f, "This cell is out of bounds!"),
+            GameError::CannotMove(mov) => match mov {
+                Move::Up => write!(f, "Cannot move up"),
+                Move::Down => write!(f, "Cannot move down"),
+                Move::Left => write!(f, "Cannot move left"),
+                Move::Right => write!(f, "Cannot move right"),
+                Move::LayerUp => write!(f, "Cannot move up a layer"),
+                Move::LayerDown => write!(f, "Cannot move down a layer"),
+                _ => write!(f, "Cannot move there"),
+            },
+            GameError::GameOver => write!(f, "Game is over"),
+            GameError::NotYourTurn => write!(f, "It is not your turn"),
+            GameError::NoMovesAvailable => write!(f, "No available moves"),
+            GameError::OpponentCannotMove(_) => write!(f, "Player is not allowed to play"),
+            GameError::NothingToUndo => write!(f, "There is no move to undo"),
+            GameError::NothingToRedo => write!(f, "There is no move to redo"),
+            GameError::BoardTooLarge => write!(f, "The board is too large to solve"),
+            GameError::WrongBoard(_) => write!(f, "You have to play in the highlighted board"),
+            GameError::ColumnFull(_) => write!(f, "This column is full!"),
+            GameError::WrongPiece(_) => write!(f, "You cannot place that piece"),
+            GameError::WrongNumber(_) => write!(f, "You cannot place that number"),
+            GameError::NoSuchLayer(_) => write!(f, "There is no such layer!"),
+            GameError::Forbidden(_) => write!(f, "This move is forbidden for the first player"),
+            GameError::InvalidWinLength(length) => write!(f, "{} in a row cannot be played on this board", length),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+/// Reasons a position string cannot be read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NotationError {
+    /// The string holds no board
+    Empty,
+    /// A row (counting from 1) has a different number of cells than the first row
+    RaggedRow { row: usize, expected: usize, found: usize },
+    /// A character that is not `X`, `O` or `.` appears in the board
+    InvalidCell(char),
+    /// The side to move is missing after the board
+    MissingSide,
+    /// The side to move is not `x` or `o`
+    InvalidSide(String),
+    /// The win length is not a number between 1 and the size of the board
+    InvalidWinLength(String),
+    /// A rule after a `+` is not one the game knows
+    InvalidRule(String),
+    /// The position is well formed but no game could have reached it
+    Unreachable(PositionError),
+    /// Something follows the last field
+    TrailingInput(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Empty => write!(f, "The position is empty"),
+            NotationError::RaggedRow { row, expected, found } => {
+                write!(f, "Row {} has {} cells, expected {}", row, found, expected)
+            }
+            NotationError::InvalidCell(c) => write!(f, "'{}' is not a cell, use X, O or .", c),
+            NotationError::MissingSide => write!(f, "The side to move is missing"),
+            NotationError::InvalidSide(side) => write!(f, "'{}' is not a side to move, use x or o", side),
+            NotationError::InvalidWinLength(length) => write!(f, "'{}' is not a valid win length", length),
+            NotationError::InvalidRule(rule) => write!(f, "'{}' is not a rule", rule),
+            NotationError::Unreachable(error) => write!(f, "The position cannot be reached: {}", error),
+            NotationError::TrailingInput(input) => write!(f, "Unexpected '{}' after the position", input),
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+/// Reasons a game record cannot be read or replayed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordError {
+    /// A header line is not of the form `[Name "value"]`
+    InvalidTag(String),
+    /// A tag holds a value that does not make sense for it
+    InvalidValue { tag: String, value: String },
+    /// A tag the record cannot do without is missing
+    MissingTag(String),
+    /// A token in the move list is not a move, time, comment or result
+    InvalidToken(String),
+    /// A comment is opened with `{` but never closed
+    UnterminatedComment,
+    /// The move with this number (counting from 1) cannot be played
+    IllegalMove { ply: usize, error: GameError },
+    /// The result written in the record is not how the moves end
+    ResultMismatch { recorded: GameResult, actual: GameResult },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::InvalidTag(line) => write!(f, "'{}' is not a valid tag", line),
+            RecordError::InvalidValue { tag, value } => write!(f, "'{}' is not a valid value for {}", value, tag),
+            RecordError::MissingTag(tag) => write!(f, "The record has no {} tag", tag),
+            RecordError::InvalidToken(token) => write!(f, "'{}' is not a move", token),
+            RecordError::UnterminatedComment => write!(f, "A comment is never closed"),
+            RecordError::IllegalMove { ply, error } => write!(f, "Move {} cannot be played: {}", ply, error),
+            RecordError::ResultMismatch { recorded, actual } => {
+                write!(f, "The record says {} but the moves end {}", recorded, actual)
+            }
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+/// Reasons a board could not have been reached by playing a game.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PositionError {
+    /// The cells do not match the width and height of the board's configuration
+    WrongSize,
+    /// Crosses move first and the players take turns, so there are as many crosses as circles or one more
+    PieceCount { crosses: usize, circles: usize },
+    /// From the number of pieces on the board, it is not this player's turn
+    WrongSide(Player),
+    /// Under gravity, the piece at this position rests on an empty cell
+    FloatingPiece(Position),
+    /// Crosses and circles both have a winning line
+    BothWon,
+    /// The game was already over before the last move, because a winning line is not the last
+    /// mover's or no single piece lies on all of them
+    PlayedAfterWin,
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::WrongSize => write!(f, "The board does not have the size it is configured with"),
+            PositionError::PieceCount { crosses, circles } => {
+                write!(
+                    f,
+                    "{} crosses and {} circles cannot be reached by taking turns",
+                    crosses, circles
+                )
+            }
+            PositionError::WrongSide(player) => write!(f, "It is not {}'s turn", player),
+            PositionError::FloatingPiece(_) => write!(f, "A piece is floating above an empty cell"),
+            PositionError::BothWon => write!(f, "Both players have a winning line"),
+            PositionError::PlayedAfterWin => write!(f, "Moves were played after the game was won"),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(GameError::CannotMove(Move::Up).to_string(), "Cannot move up");
+        assert_eq!(
+            GameError::CellOccupied(Position::default()).to_string(),
+            "This cell is already taken!"
+        );
+        assert_eq!(
+            GameError::OpponentCannotMove(Opponent::Human).to_string(),
+            "Player is not allowed to play"
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use core::fmt;
 
 use crate::{
-    player::get_pos,
+    bitboard::{BitBoard, Layout},
+    error::{GameError, PositionError},
+    gomoku,
+    player::{get_pos, get_wild_move, SharedStrategy, Strategy},
     update::{GameCell, Move, Opponent, Position, Score},
 };
 
@@ -9,15 +12,178 @@ use serde::{Deserialize, Serialize};
 
 pub type Cells = Vec<Vec<GameCell>>;
 
+/// The positions of a line of pieces, from one end to the other.
+pub type Line = Vec<Position>;
+
+/// Dimensions of a board and the number of marks in a row needed to win.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BoardConfig {
+    pub width: usize,
+    pub height: usize,
+    pub win_length: usize,
+    /// Misère rules: whoever completes a line loses
+    #[serde(default)]
+    pub misere: bool,
+    /// Gravity rules: pieces fall to the lowest empty cell of the column they are dropped in
+    #[serde(default)]
+    pub gravity: bool,
+    /// Wild rules: either player may place either piece, and whoever completes a line wins
+    #[serde(default)]
+    pub wild: bool,
+    /// Order and Chaos, with the role Player 1 plays: either player may place either piece, any
+    /// line wins for Order, and a full board without one wins for Chaos
+    #[serde(default)]
+    pub order_and_chaos: Option<Role>,
+    /// Only lines of exactly `win_length` win; longer lines, called overlines, do not
+    #[serde(default)]
+    pub exact_length: bool,
+    /// Renju restrictions on the first player: no overlines, and no move that makes two fours or
+    /// two open threes at once
+    #[serde(default)]
+    pub renju: bool,
+}
+
+impl Default for BoardConfig {
+    /// The classic 3x3 board with three in a row.
+    fn default() -> Self {
+        BoardConfig::sized(3, 3, 3)
+    }
+}
+
+impl BoardConfig {
+    /// A board of `width` by `height` cells won by `win_length` in a row, which has to be at least
+    /// one and fit on the board.
+    pub fn new(width: usize, height: usize, win_length: usize) -> Result<BoardConfig, GameError> {
+        let config = BoardConfig::sized(width, height, win_length);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn sized(width: usize, height: usize, win_length: usize) -> BoardConfig {
+        BoardConfig {
+            width,
+            height,
+            win_length,
+            misere: false,
+            gravity: false,
+            wild: false,
+            order_and_chaos: None,
+            exact_length: false,
+            renju: false,
+        }
+    }
+
+    /// Connect Four: seven columns of six cells, four in a row, with gravity.
+    pub fn connect_four() -> BoardConfig {
+        BoardConfig {
+            gravity: true,
+            ..BoardConfig::sized(7, 6, 4)
+        }
+    }
+
+    /// Order and Chaos on a 6x6 board with five in a row, Player 1 playing `role`. Order moves
+    /// first when Player 1 picks it; otherwise Chaos does.
+    pub fn order_and_chaos(role: Role) -> BoardConfig {
+        BoardConfig {
+            order_and_chaos: Some(role),
+            ..BoardConfig::sized(6, 6, 5)
+        }
+    }
+
+    /// Gomoku: five in a row on a 15x15 board. Overlines count unless `exact_length` is set, and
+    /// `renju` restricts the first player.
+    pub fn gomoku() -> BoardConfig {
+        BoardConfig::sized(15, 15, 5)
+    }
+
+    /// Checks that `win_length` is at least one and no longer than the board's longer side, so
+    /// that some line can be completed.
+    pub fn validate(&self) -> Result<(), GameError> {
+        if self.win_length == 0 || self.win_length > self.width.max(self.height) {
+            return Err(GameError::InvalidWinLength(self.win_length));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.x < self.width && pos.y < self.height
+    }
+
+    /// Whether a player may place either piece, as in wild games and Order and Chaos.
+    pub fn free_pieces(&self) -> bool {
+        self.wild || self.order_and_chaos.is_some()
+    }
+
+    /// The role `player` plays in Order and Chaos, or `None` in other games.
+    pub fn role(&self, player: Player) -> Option<Role> {
+        self.order_and_chaos.map(|role| match player {
+            Player::Player1 => role,
+            Player::Player2 => role.other(),
+        })
+    }
+
+    /// The player playing `role` in Order and Chaos, or `None` in other games.
+    pub fn player_with(&self, role: Role) -> Option<Player> {
+        [Player::Player1, Player::Player2]
+            .into_iter()
+            .find(|player| self.role(*player) == Some(role))
+    }
+
+    /// Who wins when `owner` completes a line: `owner` itself, or its opponent under misère rules.
+    /// In Order and Chaos every line wins for Order, given as its player's piece.
+    pub fn line_winner(&self, owner: GameCell) -> GameCell {
+        if let Some(order) = self.player_with(Role::Order) {
+            order.get_cell()
+        } else if self.misere {
+            owner.opposite()
+        } else {
+            owner
+        }
+    }
+
+    /// Whether a line of `cell` longer than `win_length` wins. Under renju rules the first
+    /// player's overlines never do.
+    pub fn overline_wins(&self, cell: GameCell) -> bool {
+        !(self.exact_length || self.renju && cell == GameCell::Cross)
+    }
+
+    /// The state of a full board without a line: a draw, or in Order and Chaos a win for Chaos.
+    pub fn full_board_state(&self) -> State {
+        match self.player_with(Role::Chaos) {
+            Some(chaos) => State::Win(chaos.get_cell()),
+            None => State::Draw,
+        }
+    }
+
+    /// Who has won once `mover` has left a board that [`Board::get_state`] reports as won by `cell`.
+    /// In a wild game the pieces belong to nobody, so the mover wins, or loses under misère rules.
+    pub fn winner(&self, cell: GameCell, mover: Player) -> Option<Player> {
+        match (self.wild, self.misere) {
+            (true, false) => Some(mover),
+            (true, true) => Some(mover.next()),
+            (false, _) => Player::from_cell(cell),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Board {
     pub cells: Cells,
+    #[serde(default = "BoardConfig::default")]
+    pub config: BoardConfig,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(BoardConfig::default())
+    }
 }
 
 impl Board {
-    pub fn default() -> Board {
+    pub fn new(config: BoardConfig) -> Board {
         Board {
-            cells: vec![vec![GameCell::Empty; 3]; 3],
+            cells: vec![vec![GameCell::Empty; config.width]; config.height],
+            config,
         }
     }
 
@@ -25,12 +191,16 @@ impl Board {
         self.cells.get(pos.y)?.get(pos.x)
     }
 
-    pub fn set_cell(&mut self, pos: Position, cell: GameCell) -> Result<(), String> {
-        if self.get_cell(pos) == Some(&GameCell::Empty) {
-            self.cells[pos.y][pos.x] = cell;
-            Ok(())
-        } else {
-            Err("Cell is not empty".to_string())
+    /// Places `cell` at `pos`, or under gravity at the bottom of `pos`'s column.
+    pub fn set_cell(&mut self, pos: Position, cell: GameCell) -> Result<(), GameError> {
+        let pos = self.landing(pos)?;
+        match self.get_cell(pos) {
+            Some(GameCell::Empty) => {
+                self.cells[pos.y][pos.x] = cell;
+                Ok(())
+            }
+            Some(_) => Err(GameError::CellOccupied(pos)),
+            None => Err(GameError::OutOfBounds(pos)),
         }
     }
 
@@ -38,11 +208,34 @@ impl Board {
         self.cells[pos.y][pos.x] = cell;
     }
 
+    /// Where a piece aimed at `pos` comes to rest: `pos` itself, or under gravity the lowest empty
+    /// cell of its column.
+    pub fn landing(&self, pos: Position) -> Result<Position, GameError> {
+        if !self.config.contains(pos) {
+            return Err(GameError::OutOfBounds(pos));
+        }
+        if !self.config.gravity {
+            return Ok(pos);
+        }
+        (0..self.config.height)
+            .rev()
+            .map(|y| Position { x: pos.x, y })
+            .find(|pos| self.get_cell(*pos) == Some(&GameCell::Empty))
+            .ok_or(GameError::ColumnFull(pos.x))
+    }
+
+    /// Whether a piece can rest at `pos`: always, unless gravity would pull it further down.
+    fn is_supported(&self, pos: Position) -> bool {
+        !self.config.gravity || pos.y + 1 == self.config.height || self.cells[pos.y + 1][pos.x] != GameCell::Empty
+    }
+
+    /// The empty cells a piece can be placed in, row by row. Under gravity that is the lowest
+    /// empty cell of each column that is not full.
     pub fn available_moves(&self) -> Vec<Position> {
         let mut moves = Vec::new();
-        for y in 0..3 {
-            for x in 0..3 {
-                if self.cells[y][x] == GameCell::Empty {
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                if self.cells[y][x] == GameCell::Empty && self.is_supported(Position { x, y }) {
                     moves.push(Position { y, x });
                 }
             }
@@ -51,68 +244,373 @@ impl Board {
     }
 
     pub fn moves(&self) -> usize {
-        let mut moves = 0;
-        for k in 0..3 {
-            for j in 0..3 {
-                if self.cells[k][j] != GameCell::Empty {
-                    moves += 1;
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| **cell != GameCell::Empty)
+            .count()
+    }
+
+    /// The cell `steps` cells away from `pos` in `direction`, if it is on the board.
+    pub fn offset(&self, pos: Position, (dx, dy): (isize, isize), steps: isize) -> Option<Position> {
+        let (x, y) = (pos.x as isize + dx * steps, pos.y as isize + dy * steps);
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let pos = Position {
+            x: x as usize,
+            y: y as usize,
+        };
+        self.config.contains(pos).then_some(pos)
+    }
+
+    /// The length of the unbroken run of the piece at `pos` that passes through it along
+    /// `direction`, counting both ways. Zero for an empty cell.
+    pub fn run(&self, pos: Position, direction: (isize, isize)) -> usize {
+        let cell = match self.get_cell(pos) {
+            Some(GameCell::Empty) | None => return 0,
+            Some(cell) => *cell,
+        };
+        let count = |sign: isize| {
+            (1..)
+                .take_while(|i| {
+                    self.offset(pos, direction, sign * i)
+                        .is_some_and(|pos| self.cells[pos.y][pos.x] == cell)
+                })
+                .count()
+        };
+        1 + count(1) + count(-1)
+    }
+
+    /// Whether the piece at `pos` is part of a winning line. Only the cells in line with `pos`
+    /// are looked at, so this is much cheaper than [`Board::get_state`] on a large board.
+    pub fn completes_line(&self, pos: Position) -> bool {
+        let Some(cell) = self.get_cell(pos).copied() else {
+            return false;
+        };
+        DIRECTIONS
+            .into_iter()
+            .any(|direction| self.is_winning_run(self.run(pos, direction), cell))
+    }
+
+    /// Whether an unbroken run of `length` pieces of `cell` wins.
+    fn is_winning_run(&self, length: usize, cell: GameCell) -> bool {
+        let win_length = self.config.win_length;
+        win_length > 0 && (length == win_length || (length > win_length && self.config.overline_wins(cell)))
+    }
+
+    /// Checks that playing a game could have left this board with `to_move` to play: the players
+    /// took turns starting with Player 1, nothing floats under gravity, and the game was not
+    /// already over before the last move, so that taking back one piece the last mover could
+    /// have just placed leaves no winning line.
+    pub fn validate(&self, to_move: Player) -> Result<(), PositionError> {
+        let config = self.config;
+        if self.cells.len() != config.height || self.cells.iter().any(|row| row.len() != config.width) {
+            return Err(PositionError::WrongSize);
+        }
+        let count = |cell| self.cells.iter().flatten().filter(|c| **c == cell).count();
+        let (crosses, circles) = (count(GameCell::Cross), count(GameCell::Circle));
+        // In wild games and Order and Chaos anyone may place either piece, so only the total counts
+        if !config.free_pieces() && crosses != circles && crosses != circles + 1 {
+            return Err(PositionError::PieceCount { crosses, circles });
+        }
+        let side = if (crosses + circles) % 2 == 0 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        if to_move != side {
+            return Err(PositionError::WrongSide(to_move));
+        }
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let pos = Position { x, y };
+                if self.cells[y][x] != GameCell::Empty && !self.is_supported(pos) {
+                    return Err(PositionError::FloatingPiece(pos));
                 }
             }
         }
-        moves
-    }
 
-    pub fn get_state(&self) -> State {
-        let check = |x: GameCell, y: GameCell, z: GameCell| {
-            if x == y && y == z && x != GameCell::Empty {
-                Some(x)
-            } else {
-                None
+        let lines = self.winning_lines();
+        let Some(first) = lines.first() else {
+            return Ok(());
+        };
+        let cell = self.cells[first[0].y][first[0].x];
+        if lines.iter().any(|line| self.cells[line[0].y][line[0].x] != cell) {
+            return Err(PositionError::BothWon);
+        }
+        if !config.free_pieces() && Player::from_cell(cell) != Some(side.next()) {
+            return Err(PositionError::PlayedAfterWin);
+        }
+        // Under gravity the last piece is also the top one of its column. A run longer than the
+        // win length can still win without the piece, so take it back and look again.
+        let could_be_last = |pos: &Position| {
+            if config.gravity && pos.y > 0 && self.cells[pos.y - 1][pos.x] != GameCell::Empty {
+                return false;
             }
+            let mut before = self.clone();
+            before.cells[pos.y][pos.x] = GameCell::Empty;
+            before.winning_lines().is_empty()
         };
-        let cells = self.cells.clone();
-        let rows = self
-            .cells
-            .iter()
-            .map(|row| check(row[0], row[1], row[2]))
-            .filter(|x| x.is_some())
-            .flatten()
-            .collect::<Vec<_>>();
-        let cols = (0..3)
-            .map(|col| check(cells[0][col], cells[1][col], cells[2][col]))
-            .filter(|x| x.is_some())
-            .flatten()
-            .collect::<Vec<_>>();
-        let diag1 = check(cells[0][0], cells[1][1], cells[2][2]).unwrap_or(GameCell::Empty);
-        let diag2 = check(cells[0][2], cells[1][1], cells[2][0]).unwrap_or(GameCell::Empty);
-
-        let mut state = State::Empty;
-        if rows.len() == 1 {
-            state = State::Win(rows[0]);
-        } else if cols.len() == 1 {
-            state = State::Win(cols[0]);
-        } else if diag1 != GameCell::Empty {
-            state = State::Win(diag1);
-        } else if diag2 != GameCell::Empty {
-            state = State::Win(diag2);
+        if !first.iter().any(could_be_last) {
+            return Err(PositionError::PlayedAfterWin);
+        }
+        Ok(())
+    }
+
+    /// The winning lines on the board, each as the whole unbroken run it is part of. A move that
+    /// completes two lines at once leaves two; while nobody has won there are none. Under misère
+    /// rules these are the lines that lost.
+    pub fn winning_lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                let pos = Position { x, y };
+                let cell = self.cells[y][x];
+                if cell == GameCell::Empty {
+                    continue;
+                }
+                for direction in DIRECTIONS {
+                    // Each run is only collected from its first cell
+                    let continues = self
+                        .offset(pos, direction, -1)
+                        .is_some_and(|before| self.cells[before.y][before.x] == cell);
+                    let run = self.run(pos, direction);
+                    if !continues && self.is_winning_run(run, cell) {
+                        lines.push(
+                            (0..run as isize)
+                                .filter_map(|i| self.offset(pos, direction, i))
+                                .collect(),
+                        );
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// The state of the board just after a piece was placed at `pos` on a board that was still
+    /// undecided, worked out from the lines through `pos` alone.
+    pub fn state_after(&self, pos: Position) -> State {
+        if self.completes_line(pos) {
+            State::Win(self.config.line_winner(self.cells[pos.y][pos.x]))
         } else if self.available_moves().is_empty() {
-            state = State::Draw;
+            self.config.full_board_state()
+        } else {
+            State::Empty
+        }
+    }
+
+    /// Every horizontal, vertical and diagonal run of `win_length` cells on the board.
+    pub fn lines(&self) -> Vec<Vec<Position>> {
+        let mut lines = Vec::new();
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                for direction in DIRECTIONS {
+                    if let Some(line) = self.line_from(Position { x, y }, direction) {
+                        lines.push(line);
+                    }
+                }
+            }
         }
+        lines
+    }
+
+    /// The run of `win_length` cells starting at `start` and heading in `direction`, if it fits on the board.
+    fn line_from(&self, start: Position, (dx, dy): (isize, isize)) -> Option<Vec<Position>> {
+        let length = self.config.win_length as isize;
+        if length == 0 {
+            return None;
+        }
+        let end_x = start.x as isize + dx * (length - 1);
+        let end_y = start.y as isize + dy * (length - 1);
+        if end_x < 0
+            || end_y < 0
+            || !self.config.contains(Position {
+                x: end_x as usize,
+                y: end_y as usize,
+            })
+        {
+            return None;
+        }
+        Some(
+            (0..length)
+                .map(|i| Position {
+                    x: (start.x as isize + dx * i) as usize,
+                    y: (start.y as isize + dy * i) as usize,
+                })
+                .collect(),
+        )
+    }
+
+    /// The board packed into one bitmask per side, if it has no more than [`bitboard::MAX_CELLS`](crate::bitboard::MAX_CELLS) cells.
+    pub fn to_bitboard(&self) -> Option<BitBoard> {
+        let layout = Layout::get(self.config)?;
+        BitBoard::from_cells(&self.cells, &layout)
+    }
+
+    /// Unpacks a bitboard laid out for `config`.
+    pub fn from_bitboard(bits: BitBoard, config: BoardConfig) -> Option<Board> {
+        let layout = Layout::get(config)?;
+        Some(Board {
+            cells: bits.to_cells(&layout),
+            config,
+        })
+    }
 
-        state
+    /// The board after rotating or reflecting it with `transform`.
+    pub fn transform(&self, transform: Transform) -> Board {
+        let mut board = Board::new(transform.apply_config(self.config));
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                board.set_cell_force(transform.apply(Position { x, y }, self.config), *cell);
+            }
+        }
+        board
+    }
+
+    /// The representative of every board that is a rotation or reflection of this one, together
+    /// with the transform that turns this board into it.
+    ///
+    /// Two boards have the same canonical form exactly when one is a symmetry of the other.
+    pub fn canonical(&self) -> (Board, Transform) {
+        Transform::symmetries(self.config)
+            .into_iter()
+            .map(|transform| (self.transform(transform), transform))
+            .min_by(|(a, _), (b, _)| a.cells.cmp(&b.cells))
+            .unwrap()
+    }
+
+    pub fn get_state(&self) -> State {
+        if let Some(layout) = Layout::get(self.config) {
+            if let Some(bits) = BitBoard::from_cells(&self.cells, &layout) {
+                return bits.get_state(&layout);
+            }
+        }
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                let pos = Position { x, y };
+                if self.cells[y][x] != GameCell::Empty && self.completes_line(pos) {
+                    return State::Win(self.config.line_winner(self.cells[y][x]));
+                }
+            }
+        }
+        if self.available_moves().is_empty() {
+            self.config.full_board_state()
+        } else {
+            State::Empty
+        }
+    }
+}
+
+/// Directions a line can run in: right, down, down-right and up-right.
+pub(crate) const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// A rotation or reflection of the board. Rotations are clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors left and right
+    FlipHorizontal,
+    /// Mirrors top and bottom
+    FlipVertical,
+    /// Mirrors along the top-left to bottom-right diagonal
+    FlipDiagonal,
+    /// Mirrors along the top-right to bottom-left diagonal
+    FlipAntiDiagonal,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::FlipDiagonal,
+        Transform::FlipAntiDiagonal,
+    ];
+
+    /// The transforms that leave a board of this shape the same shape: all eight for a square
+    /// board, otherwise only those that do not swap rows and columns. Under gravity only the
+    /// mirror image keeps the pieces resting on the bottom.
+    pub fn symmetries(config: BoardConfig) -> Vec<Transform> {
+        if config.gravity {
+            return vec![Transform::Identity, Transform::FlipHorizontal];
+        }
+        Transform::ALL
+            .into_iter()
+            .filter(|transform| config.width == config.height || !transform.swaps_axes())
+            .collect()
+    }
+
+    fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90 | Transform::Rotate270 | Transform::FlipDiagonal | Transform::FlipAntiDiagonal
+        )
+    }
+
+    pub fn inverse(&self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => *other,
+        }
+    }
+
+    /// The shape of a board with shape `config` after this transform.
+    pub fn apply_config(&self, config: BoardConfig) -> BoardConfig {
+        if self.swaps_axes() {
+            BoardConfig {
+                width: config.height,
+                height: config.width,
+                ..config
+            }
+        } else {
+            config
+        }
+    }
+
+    /// Where `pos` on a board with shape `config` ends up after this transform.
+    pub fn apply(&self, pos: Position, config: BoardConfig) -> Position {
+        let (w, h) = (config.width, config.height);
+        let (x, y) = (pos.x, pos.y);
+        let (x, y) = match self {
+            Transform::Identity => (x, y),
+            Transform::Rotate90 => (h - 1 - y, x),
+            Transform::Rotate180 => (w - 1 - x, h - 1 - y),
+            Transform::Rotate270 => (y, w - 1 - x),
+            Transform::FlipHorizontal => (w - 1 - x, y),
+            Transform::FlipVertical => (x, h - 1 - y),
+            Transform::FlipDiagonal => (y, x),
+            Transform::FlipAntiDiagonal => (h - 1 - y, w - 1 - x),
+        };
+        Position { x, y }
+    }
+
+    /// Where `pos` on a transformed board came from on a board with shape `config`.
+    pub fn revert(&self, pos: Position, config: BoardConfig) -> Position {
+        self.inverse().apply(pos, self.apply_config(config))
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum State {
     Empty,
+    /// The game is won by this cell's player, who under misère rules is not the one who completed the line
     Win(GameCell),
     Draw,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum GameState {
-    GameOver(Option<Player>, Cells),
+    /// The winner, if any, the final board and the lines that decided it
+    GameOver(Option<Player>, Cells, Vec<Line>),
     GameInProgress(Cells, Player, Position),
 }
 
@@ -136,6 +634,15 @@ impl Player {
             Player::Player2 => GameCell::Circle,
         }
     }
+
+    /// The player whose pieces are `cell`.
+    pub fn from_cell(cell: GameCell) -> Option<Player> {
+        match cell {
+            GameCell::Cross => Some(Player::Player1),
+            GameCell::Circle => Some(Player::Player2),
+            GameCell::Empty => None,
+        }
+    }
 }
 
 impl fmt::Display for Player {
@@ -147,6 +654,59 @@ impl fmt::Display for Player {
     }
 }
 
+/// The two sides of Order and Chaos, which take the place of the usual crosses and circles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Role {
+    /// Wins by getting five of the same piece in a row
+    Order,
+    /// Wins by filling the board without five in a row
+    Chaos,
+}
+
+impl Role {
+    pub fn other(&self) -> Role {
+        match self {
+            Role::Order => Role::Chaos,
+            Role::Chaos => Role::Order,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Order => write!(f, "Order"),
+            Role::Chaos => write!(f, "Chaos"),
+        }
+    }
+}
+
+/// A piece placed during the game: who played it, where, and which piece it was.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ply {
+    pub player: Player,
+    pub position: Position,
+    pub cell: GameCell,
+}
+
+/// Who plays as Player 2: one of the built-in opponents or your own [`Strategy`].
+pub enum Challenger {
+    Opponent(Opponent),
+    Strategy(Box<dyn Strategy>),
+}
+
+impl From<Opponent> for Challenger {
+    fn from(opponent: Opponent) -> Self {
+        Challenger::Opponent(opponent)
+    }
+}
+
+impl From<Box<dyn Strategy>> for Challenger {
+    fn from(strategy: Box<dyn Strategy>) -> Self {
+        Challenger::Strategy(strategy)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Game {
     pub board: Board,
@@ -156,19 +716,78 @@ pub struct Game {
     pub opponent: Opponent,
     should_continue: bool,
     state_changed: bool,
+    #[serde(default)]
+    history: Vec<Ply>,
+    #[serde(default)]
+    undone: Vec<Ply>,
+    /// Only set for [`Opponent::Custom`]; built-in opponents are looked up from `opponent`
+    #[serde(skip)]
+    strategy: Option<SharedStrategy>,
 }
 
 impl Game {
-    pub fn new(opponent: Opponent) -> Game {
+    pub fn new(opponent: impl Into<Challenger>) -> Game {
+        Game::start(opponent, BoardConfig::default())
+    }
+
+    /// A game on a board shaped by `config`, rejected if [`BoardConfig::validate`] rejects it.
+    pub fn with_config(opponent: impl Into<Challenger>, config: BoardConfig) -> Result<Game, GameError> {
+        config.validate()?;
+        Ok(Game::start(opponent, config))
+    }
+
+    fn start(opponent: impl Into<Challenger>, config: BoardConfig) -> Game {
+        let (opponent, strategy) = match opponent.into() {
+            Challenger::Opponent(opponent) => (opponent, None),
+            Challenger::Strategy(strategy) => (Opponent::Custom, Some(SharedStrategy::new(strategy))),
+        };
         Game {
-            board: Board::default(),
+            board: Board::new(config),
             current_position: Position::default(),
             current_player: Player::Player1,
             winner: None,
             opponent,
             should_continue: true,
             state_changed: true,
+            history: Vec::new(),
+            undone: Vec::new(),
+            strategy,
+        }
+    }
+
+    /// A game continuing from `board` with `current_player` to move. A board that is already won
+    /// or drawn gives a finished game, won by whoever owns the completed line, or in a wild game by
+    /// whoever moved last.
+    pub(crate) fn with_position(opponent: impl Into<Challenger>, board: Board, current_player: Player) -> Game {
+        let mut game = Game::start(opponent, board.config);
+        game.board = board;
+        game.current_player = current_player;
+        match game.board.get_state() {
+            State::Win(cell) => {
+                game.winner = game.board.config.winner(cell, current_player.next());
+                game.should_continue = false;
+            }
+            State::Draw => game.should_continue = false,
+            State::Empty => {}
+        }
+        game
+    }
+
+    /// A game continuing from `board` with `to_move` to play, rejecting boards that no game could
+    /// have reached, as [`Board::validate`] does. A board that is already won or drawn gives a
+    /// finished game and, as after the move that ends a game, the current player is the one who
+    /// moved last.
+    pub fn from_position(
+        opponent: impl Into<Challenger>,
+        board: Board,
+        to_move: Player,
+    ) -> Result<Game, PositionError> {
+        board.validate(to_move)?;
+        let mut game = Game::with_position(opponent, board, to_move);
+        if game.is_over() {
+            game.current_player = to_move.next();
         }
+        Ok(game)
     }
 
     fn get_current_player_cell(&self) -> GameCell {
@@ -178,7 +797,7 @@ impl Game {
         }
     }
 
-    pub fn update(&mut self, mov: Move) -> Result<GameState, String> {
+    pub fn update(&mut self, mov: Move) -> Result<GameState, GameError> {
         match mov {
             Move::Up => {
                 if self.current_position.y > 0 {
@@ -187,17 +806,17 @@ impl Game {
                     self.state_changed = true;
                     Ok(self.get_state().unwrap())
                 } else {
-                    Err("Cannot move up".to_string())
+                    Err(GameError::CannotMove(mov))
                 }
             }
             Move::Down => {
-                if self.current_position.y < 2 {
+                if self.current_position.y + 1 < self.board.config.height {
                     self.current_position.y += 1;
 
                     self.state_changed = true;
                     Ok(self.get_state().unwrap())
                 } else {
-                    Err("Cannot move down".to_string())
+                    Err(GameError::CannotMove(mov))
                 }
             }
             Move::Left => {
@@ -207,17 +826,17 @@ impl Game {
                     self.state_changed = true;
                     Ok(self.get_state().unwrap())
                 } else {
-                    Err("Cannot move left".to_string())
+                    Err(GameError::CannotMove(mov))
                 }
             }
             Move::Right => {
-                if self.current_position.x < 2 {
+                if self.current_position.x + 1 < self.board.config.width {
                     self.current_position.x += 1;
 
                     self.state_changed = true;
                     Ok(self.get_state().unwrap())
                 } else {
-                    Err("Cannot move right".to_string())
+                    Err(GameError::CannotMove(mov))
                 }
             }
             Move::Place => match self.place() {
@@ -227,43 +846,97 @@ impl Game {
                 }
                 Err(e) => Err(e),
             },
-            Move::PlaceAt(pos) => match self.board.set_cell(pos, self.get_current_player_cell()) {
+            Move::PlaceAt(pos) => match self.play(pos, self.get_current_player_cell()) {
+                Ok(_) => {
+                    self.state_changed = true;
+                    Ok(self.get_state().unwrap())
+                }
+                Err(e) => Err(e),
+            },
+            Move::PlaceWith(cell) => match self.play(self.current_position, cell) {
+                Ok(_) => {
+                    self.state_changed = true;
+                    Ok(self.get_state().unwrap())
+                }
+                Err(e) => Err(e),
+            },
+            Move::PlaceAtWith(pos, cell) => match self.play(pos, cell) {
                 Ok(_) => {
                     self.state_changed = true;
                     Ok(self.get_state().unwrap())
                 }
                 Err(e) => Err(e),
             },
+            Move::PlaceNumber(number) | Move::PlaceNumberAt(_, number) => Err(GameError::WrongNumber(number)),
+            // A flat board has a single layer
+            Move::LayerUp | Move::LayerDown | Move::PlaceAt3(_) => Err(GameError::CannotMove(mov)),
         }
     }
 
-    fn opponent_move(&mut self) -> Result<(), String> {
-        let pos = match get_pos(self.opponent, &self.board, &self.current_player.get_cell()) {
-            Ok(pos) => pos,
-            Err(e) => return Err(e),
+    fn opponent_move(&mut self) -> Result<(), GameError> {
+        let cell = self.current_player.get_cell();
+        let (pos, cell) = if self.board.config.free_pieces() {
+            match &self.strategy {
+                Some(strategy) => strategy.choose_wild(&self.board, cell)?,
+                None => get_wild_move(self.opponent, &self.board, cell)?,
+            }
+        } else {
+            let pos = match &self.strategy {
+                Some(strategy) => strategy.choose(&self.board, cell)?,
+                None => get_pos(self.opponent, &self.board, &cell)?,
+            };
+            (pos, cell)
         };
-        match self.board.set_cell(pos, self.current_player.get_cell()) {
-            Ok(_) => self.next(),
-            Err(e) => Err(e),
+        self.play(pos, cell)
+    }
+
+    /// Places `cell` at `pos`, or where it lands under gravity, records it and hands the turn over.
+    /// Only wild games and Order and Chaos let a player place the other player's piece.
+    fn play(&mut self, pos: Position, cell: GameCell) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        self.check_opponent()?;
+        if cell == GameCell::Empty || (!self.board.config.free_pieces() && cell != self.get_current_player_cell()) {
+            return Err(GameError::WrongPiece(cell));
         }
+        let pos = self.board.landing(pos)?;
+        if self.board.config.renju && cell == GameCell::Cross && gomoku::is_forbidden(&self.board, pos) {
+            return Err(GameError::Forbidden(pos));
+        }
+        self.board.set_cell(pos, cell)?;
+        self.history.push(Ply {
+            player: self.current_player,
+            position: pos,
+            cell,
+        });
+        self.undone.clear();
+        self.next()
     }
 
-    fn next(&mut self) -> Result<(), String> {
-        match self.board.get_state() {
+    fn next(&mut self) -> Result<(), GameError> {
+        self.settle();
+        if self.should_continue && self.plays_computer() {
+            if let Player::Player2 = self.current_player {
+                return self.opponent_move();
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the turn and result after a piece has been placed, without letting the computer reply.
+    fn settle(&mut self) {
+        let state = match self.history.last() {
+            Some(ply) => self.board.state_after(ply.position),
+            None => self.board.get_state(),
+        };
+        match state {
             State::Empty => {
                 self.current_player = self.current_player.next();
                 self.state_changed = true;
-                if let Opponent::Human = self.opponent {
-                    return Ok(());
-                } else if let Opponent::Online = self.opponent {
-                    return Ok(());
-                }
-                if let Player::Player2 = self.current_player {
-                    return self.opponent_move();
-                }
             }
-            State::Win(_) => {
-                self.winner = Some(self.current_player);
+            State::Win(cell) => {
+                self.winner = self.board.config.winner(cell, self.current_player);
                 self.should_continue = false;
             }
             State::Draw => {
@@ -271,6 +944,57 @@ impl Game {
                 self.should_continue = false;
             }
         }
+    }
+
+    /// Fails if the computer would have to reply but has nothing to play with, as for an
+    /// [`Opponent::Custom`] game whose strategy was lost when the game was deserialised.
+    fn check_opponent(&self) -> Result<(), GameError> {
+        if self.opponent == Opponent::Custom && self.strategy.is_none() {
+            return Err(GameError::OpponentCannotMove(self.opponent));
+        }
+        Ok(())
+    }
+
+    /// Whether Player 2 is controlled by the computer.
+    fn plays_computer(&self) -> bool {
+        !matches!(self.opponent, Opponent::Human | Opponent::Online)
+    }
+
+    /// The pieces placed so far, in the order they were played.
+    pub fn history(&self) -> &[Ply] {
+        &self.history
+    }
+
+    /// Takes back the last move. Against a computer opponent its reply is taken back as well,
+    /// so it is Player 1's turn again.
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        let ply = self.history.pop().ok_or(GameError::NothingToUndo)?;
+        self.board.set_cell_force(ply.position, GameCell::Empty);
+        self.current_player = ply.player;
+        self.winner = None;
+        self.should_continue = true;
+        self.state_changed = true;
+        self.undone.push(ply);
+        if self.plays_computer() && self.current_player == Player::Player2 {
+            return self.undo();
+        }
+        Ok(())
+    }
+
+    /// Plays back the last move taken back with [`Game::undo`], including the computer's reply.
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        self.check_opponent()?;
+        let ply = self.undone.pop().ok_or(GameError::NothingToRedo)?;
+        self.board.set_cell_force(ply.position, ply.cell);
+        self.current_player = ply.player;
+        self.history.push(ply);
+        self.settle();
+        if self.should_continue && self.plays_computer() && self.current_player == Player::Player2 {
+            if self.undone.is_empty() {
+                return self.opponent_move();
+            }
+            return self.redo();
+        }
         Ok(())
     }
     pub fn is_over(&self) -> bool {
@@ -287,31 +1011,23 @@ impl Game {
             Score::default()
         }
     }
-    pub fn place(&mut self) -> Result<(), String> {
-        self.state_changed = true;
-        if let Some(cell) = self.board.get_cell(self.current_position) {
-            match cell {
-                GameCell::Empty => {
-                    match self
-                        .board
-                        .set_cell(self.current_position, self.get_current_player_cell())
-                    {
-                        Ok(_) => self.next(),
-                        Err(e) => Err(e),
-                    }
-                }
-                _ => Err("This cell is already taken!".to_string()),
-            }
-        } else {
-            Err("This cell is out of bounds!".to_string())
+    pub fn place(&mut self) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
         }
+        self.state_changed = true;
+        self.play(self.current_position, self.get_current_player_cell())
     }
 
     pub fn get_state(&mut self) -> Option<GameState> {
         if self.state_changed {
             self.state_changed = false;
             if self.is_over() {
-                Some(GameState::GameOver(self.winner, self.board.cells.clone()))
+                Some(GameState::GameOver(
+                    self.winner,
+                    self.board.cells.clone(),
+                    self.board.winning_lines(),
+                ))
             } else {
                 Some(GameState::GameInProgress(
                     self.board.cells.clone(),
@@ -424,4 +1140,507 @@ mod tests {
         assert_eq!(game.board.available_moves().contains(&Position { x: 0, y: 0 }), false);
         assert_eq!(game.board.available_moves().contains(&Position { x: 0, y: 2 }), false);
     }
+
+    #[test]
+    fn test_board_config() {
+        let board = Board::new(BoardConfig::new(5, 4, 4).unwrap());
+        assert_eq!(board.cells.len(), 4);
+        assert_eq!(board.cells[0].len(), 5);
+        assert_eq!(board.available_moves().len(), 20);
+        // 8 horizontal, 5 vertical and 2 + 2 diagonal runs of four
+        assert_eq!(board.lines().len(), 17);
+
+        // A line has to fit along the longer side
+        assert!(BoardConfig::new(5, 1, 5).is_ok());
+        assert_eq!(BoardConfig::new(5, 4, 6), Err(GameError::InvalidWinLength(6)));
+        assert_eq!(BoardConfig::new(3, 3, 0), Err(GameError::InvalidWinLength(0)));
+        let config = BoardConfig {
+            win_length: 4,
+            ..BoardConfig::default()
+        };
+        assert_eq!(
+            Game::with_config(Opponent::Human, config),
+            Err(GameError::InvalidWinLength(4))
+        );
+    }
+
+    #[test]
+    fn test_k_in_a_row() {
+        let mut board = Board::new(BoardConfig::new(5, 5, 4).unwrap());
+        for x in 1..4 {
+            board.set_cell(Position { x, y: 1 }, GameCell::Cross).unwrap();
+        }
+        assert_eq!(board.get_state(), State::Empty);
+        board.set_cell(Position { x: 4, y: 1 }, GameCell::Cross).unwrap();
+        assert_eq!(board.get_state(), State::Win(GameCell::Cross));
+
+        let mut board = Board::new(BoardConfig::new(5, 5, 4).unwrap());
+        for i in 0..4 {
+            board
+                .set_cell(Position { x: 4 - i, y: i + 1 }, GameCell::Circle)
+                .unwrap();
+        }
+        assert_eq!(board.get_state(), State::Win(GameCell::Circle));
+    }
+
+    #[test]
+    fn test_cursor_bounds() {
+        let mut game = Game::with_config(Opponent::Human, BoardConfig::new(4, 4, 4).unwrap()).unwrap();
+        for _ in 0..3 {
+            game.update(Move::Right).unwrap();
+            game.update(Move::Down).unwrap();
+        }
+        assert_eq!(game.current_position, Position { x: 3, y: 3 });
+        assert!(game.update(Move::Right).is_err());
+        assert!(game.update(Move::Down).is_err());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::new(Opponent::Human);
+        for x in [0, 0, 1, 1, 2] {
+            let y = game.history().len() % 2;
+            game.update(Move::PlaceAt(Position { x, y })).unwrap();
+        }
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player1));
+        assert_eq!(game.history().len(), 5);
+
+        game.undo().unwrap();
+        assert!(!game.is_over());
+        assert_eq!(game.winner, None);
+        assert_eq!(game.current_player, Player::Player1);
+        assert_eq!(game.board.get_cell(Position { x: 2, y: 0 }), Some(&GameCell::Empty));
+
+        game.undo().unwrap();
+        assert_eq!(game.current_player, Player::Player2);
+        assert_eq!(game.history().len(), 3);
+
+        game.redo().unwrap();
+        game.redo().unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player1));
+        assert_eq!(game.redo(), Err(GameError::NothingToRedo));
+
+        game.undo().unwrap();
+        game.update(Move::PlaceAt(Position { x: 2, y: 2 })).unwrap();
+        assert_eq!(game.redo(), Err(GameError::NothingToRedo));
+    }
+
+    #[test]
+    fn test_undo_against_computer() {
+        let mut game = Game::new(Opponent::Random);
+        assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+        game.place().unwrap();
+        let reply = game.history()[1];
+        assert_eq!(reply.player, Player::Player2);
+
+        game.undo().unwrap();
+        assert_eq!(game.current_player, Player::Player1);
+        assert_eq!(game.board.moves(), 0);
+        assert!(game.history().is_empty());
+
+        game.redo().unwrap();
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(game.history()[1], reply);
+        assert_eq!(game.current_player, Player::Player1);
+    }
+
+    #[test]
+    fn test_custom_strategy() {
+        struct FirstFree;
+        impl Strategy for FirstFree {
+            fn choose(&mut self, board: &Board, _cell: GameCell) -> Result<Position, GameError> {
+                board
+                    .available_moves()
+                    .first()
+                    .copied()
+                    .ok_or(GameError::NoMovesAvailable)
+            }
+        }
+
+        let mut game = Game::new(Box::new(FirstFree) as Box<dyn Strategy>);
+        assert_eq!(game.opponent, Opponent::Custom);
+        game.current_position = Position { x: 1, y: 1 };
+        game.place().unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 0, y: 0 }), Some(&GameCell::Circle));
+        assert_eq!(game.current_player, Player::Player1);
+        assert_eq!(game.clone(), game);
+
+        // The strategy is not serialised, so without it no move is played at all
+        let mut restored = game.clone();
+        restored.strategy = None;
+        assert_eq!(
+            restored.update(Move::PlaceAt(Position { x: 2, y: 2 })),
+            Err(GameError::OpponentCannotMove(Opponent::Custom))
+        );
+        assert_eq!(restored.history(), game.history());
+        assert_eq!(restored.current_player, Player::Player1);
+    }
+
+    #[test]
+    fn test_transform_round_trip() {
+        for config in [BoardConfig::default(), BoardConfig::new(4, 3, 3).unwrap()] {
+            for transform in Transform::ALL {
+                let moved = transform.apply_config(config);
+                for pos in Board::new(config).available_moves() {
+                    let image = transform.apply(pos, config);
+                    assert!(moved.contains(image));
+                    assert_eq!(transform.revert(image, config), pos);
+                }
+            }
+            let expected = if config.width == config.height { 8 } else { 4 };
+            assert_eq!(Transform::symmetries(config).len(), expected);
+        }
+        let corner = Position { x: 0, y: 0 };
+        assert_eq!(
+            Transform::Rotate90.apply(corner, BoardConfig::default()),
+            Position { x: 2, y: 0 }
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        let mut corners = Vec::new();
+        for (x, y) in [(0, 0), (2, 0), (0, 2), (2, 2)] {
+            let mut board = Board::default();
+            board.set_cell(Position { x, y }, GameCell::Cross).unwrap();
+            corners.push(board);
+        }
+        let (canonical, _) = corners[0].canonical();
+        for board in &corners {
+            let (form, transform) = board.canonical();
+            assert_eq!(form, canonical);
+            assert_eq!(board.transform(transform), form);
+            // The piece in the canonical form maps back onto the original corner
+            let played = (0..9)
+                .map(|i| Position { x: i % 3, y: i / 3 })
+                .find(|pos| form.get_cell(*pos) == Some(&GameCell::Cross))
+                .unwrap();
+            assert_eq!(
+                board.get_cell(transform.revert(played, board.config)),
+                Some(&GameCell::Cross)
+            );
+        }
+
+        let mut edge = Board::default();
+        edge.set_cell(Position { x: 1, y: 0 }, GameCell::Cross).unwrap();
+        assert_ne!(edge.canonical().0, canonical);
+    }
+
+    #[test]
+    fn test_gravity() {
+        let mut game = Game::with_config(Opponent::Human, BoardConfig::connect_four()).unwrap();
+        assert_eq!(game.board.available_moves().len(), 7);
+        assert!(game.board.available_moves().iter().all(|pos| pos.y == 5));
+
+        game.update(Move::PlaceAt(Position { x: 3, y: 0 })).unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 3, y: 5 }), Some(&GameCell::Cross));
+        assert_eq!(game.history()[0].position, Position { x: 3, y: 5 });
+        // The cursor drops into its column too
+        game.update(Move::Right).unwrap();
+        game.update(Move::Place).unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 1, y: 5 }), Some(&GameCell::Circle));
+        assert!(game.board.available_moves().contains(&Position { x: 3, y: 4 }));
+        assert!(!game.board.available_moves().contains(&Position { x: 3, y: 5 }));
+
+        for _ in 0..3 {
+            game.update(Move::PlaceAt(Position { x: 0, y: 0 })).unwrap();
+            game.update(Move::PlaceAt(Position { x: 0, y: 0 })).unwrap();
+        }
+        assert_eq!(
+            game.update(Move::PlaceAt(Position { x: 0, y: 0 })),
+            Err(GameError::ColumnFull(0))
+        );
+        assert_eq!(
+            game.update(Move::PlaceAt(Position { x: 7, y: 0 })),
+            Err(GameError::OutOfBounds(Position { x: 7, y: 0 }))
+        );
+
+        // Four stacked in the last column wins
+        for _ in 0..3 {
+            game.update(Move::PlaceAt(Position { x: 6, y: 0 })).unwrap();
+            game.update(Move::PlaceAt(Position { x: 5, y: 0 })).unwrap();
+        }
+        game.update(Move::PlaceAt(Position { x: 6, y: 0 })).unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player1));
+    }
+
+    #[test]
+    fn test_wild() {
+        let config = BoardConfig {
+            wild: true,
+            ..BoardConfig::default()
+        };
+        let mut game = Game::with_config(Opponent::Human, config).unwrap();
+        game.update(Move::PlaceAtWith(Position { x: 0, y: 0 }, GameCell::Circle))
+            .unwrap();
+        game.update(Move::PlaceAtWith(Position { x: 1, y: 0 }, GameCell::Circle))
+            .unwrap();
+        assert_eq!(
+            game.update(Move::PlaceAtWith(Position { x: 2, y: 0 }, GameCell::Empty)),
+            Err(GameError::WrongPiece(GameCell::Empty))
+        );
+        game.undo().unwrap();
+        game.redo().unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 1, y: 0 }), Some(&GameCell::Circle));
+        assert_eq!(game.history()[1].cell, GameCell::Circle);
+
+        // Player 1 completes the row of circles and wins with them
+        game.update(Move::PlaceAtWith(Position { x: 2, y: 0 }, GameCell::Circle))
+            .unwrap();
+        assert_eq!(game.winner, Some(Player::Player1));
+        assert_eq!(game.get_score(), Score { player1: 1, player2: 0 });
+
+        let mut game = Game::new(Opponent::Human);
+        assert_eq!(
+            game.update(Move::PlaceWith(GameCell::Circle)),
+            Err(GameError::WrongPiece(GameCell::Circle))
+        );
+        game.update(Move::PlaceWith(GameCell::Cross)).unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 0, y: 0 }), Some(&GameCell::Cross));
+    }
+
+    #[test]
+    fn test_wild_against_computer() {
+        let config = BoardConfig {
+            wild: true,
+            ..BoardConfig::default()
+        };
+        let mut game = Game::with_config(Opponent::Minimax, config).unwrap();
+        game.update(Move::PlaceAtWith(Position { x: 0, y: 0 }, GameCell::Circle))
+            .unwrap();
+        assert_eq!(game.history().len(), 2);
+        // With a single piece in the corner, the computer must not allow three in a row next move
+        let reply = game.history()[1];
+        assert_eq!(reply.player, Player::Player2);
+        assert!(!game.is_over());
+        while !game.is_over() {
+            let pos = game.board.available_moves()[0];
+            game.update(Move::PlaceAtWith(pos, GameCell::Cross)).unwrap();
+        }
+        assert_ne!(game.winner, Some(Player::Player1));
+    }
+
+    #[test]
+    fn test_order_and_chaos() {
+        let config = BoardConfig::order_and_chaos(Role::Chaos);
+        assert_eq!(config.role(Player::Player2), Some(Role::Order));
+        assert_eq!(config.player_with(Role::Chaos), Some(Player::Player1));
+        assert_eq!(BoardConfig::default().role(Player::Player1), None);
+
+        let mut game = Game::with_config(Opponent::Human, config).unwrap();
+        for x in 0..4 {
+            game.board.set_cell_force(Position { x, y: 0 }, GameCell::Cross);
+        }
+        // Chaos blocks with a circle, then Order completes the next row with Player 1's crosses
+        game.update(Move::PlaceAtWith(Position { x: 4, y: 0 }, GameCell::Circle))
+            .unwrap();
+        for x in 0..4 {
+            game.board.set_cell_force(Position { x, y: 1 }, GameCell::Cross);
+        }
+        game.update(Move::PlaceAtWith(Position { x: 4, y: 1 }, GameCell::Cross))
+            .unwrap();
+        assert_eq!(game.board.get_state(), State::Win(GameCell::Circle));
+        assert_eq!(game.winner, Some(Player::Player2));
+
+        // A full board without five in a row wins for Chaos
+        let mut board = Board::new(config);
+        for y in 0..6 {
+            for x in 0..6 {
+                let cell = if (x + 2 * y) % 4 < 2 {
+                    GameCell::Cross
+                } else {
+                    GameCell::Circle
+                };
+                board.set_cell_force(Position { x, y }, cell);
+            }
+        }
+        assert_eq!(board.get_state(), State::Win(GameCell::Cross));
+        board.config.order_and_chaos = None;
+        assert_eq!(board.get_state(), State::Draw);
+    }
+
+    #[test]
+    fn test_misere() {
+        let config = BoardConfig {
+            misere: true,
+            ..BoardConfig::default()
+        };
+        let mut game = Game::with_config(Opponent::Human, config).unwrap();
+        for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            game.update(Move::PlaceAt(Position { x, y })).unwrap();
+        }
+        assert!(!game.is_over());
+        // Player 1 completes the top row and loses
+        game.update(Move::PlaceAt(Position { x: 2, y: 0 })).unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player2));
+        assert_eq!(game.get_score(), Score { player1: 0, player2: 1 });
+        assert_eq!(game.board.get_state(), State::Win(GameCell::Circle));
+    }
+
+    #[test]
+    fn test_winning_lines() {
+        let mut game = Game::new(Opponent::Human);
+        for (x, y) in [(0, 0), (2, 1), (1, 0), (0, 1), (1, 1), (1, 2), (0, 2), (2, 2)] {
+            game.update(Move::PlaceAt(Position { x, y })).unwrap();
+        }
+        assert!(game.board.winning_lines().is_empty());
+        // The last cross completes the top row and a diagonal at once
+        let state = game.update(Move::PlaceAt(Position { x: 2, y: 0 })).unwrap();
+        let row = vec![
+            Position { x: 0, y: 0 },
+            Position { x: 1, y: 0 },
+            Position { x: 2, y: 0 },
+        ];
+        let diagonal = vec![
+            Position { x: 0, y: 2 },
+            Position { x: 1, y: 1 },
+            Position { x: 2, y: 0 },
+        ];
+        assert_eq!(
+            state,
+            GameState::GameOver(Some(Player::Player1), game.board.cells.clone(), vec![row, diagonal])
+        );
+
+        // A run longer than needed is reported whole
+        let mut board = Board::new(BoardConfig::new(4, 1, 3).unwrap());
+        for x in 0..4 {
+            board.set_cell_force(Position { x, y: 0 }, GameCell::Circle);
+        }
+        let run = (0..4).map(|x| Position { x, y: 0 }).collect::<Line>();
+        assert_eq!(board.winning_lines(), vec![run]);
+    }
+
+    #[test]
+    fn test_from_position() {
+        let position = |text: &str| text.parse::<Board>().unwrap();
+        let game = Game::from_position(Opponent::Human, position("X.O/.X./..."), Player::Player2).unwrap();
+        assert_eq!(game.current_player, Player::Player2);
+        assert!(!game.is_over());
+
+        // A finished game looks as it would right after its last move
+        let board = position("XXX/OO./...");
+        let game = Game::from_position(Opponent::Human, board.clone(), Player::Player2).unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player1));
+        assert_eq!(game.current_player, Player::Player1);
+        let mut played = Game::new(Opponent::Human);
+        for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)] {
+            played.update(Move::PlaceAt(Position { x, y })).unwrap();
+        }
+        assert_eq!(played.board, board);
+        assert_eq!(played.current_player, game.current_player);
+
+        let errors = [
+            (
+                "XXX/.../...",
+                Player::Player2,
+                PositionError::PieceCount { crosses: 3, circles: 0 },
+            ),
+            (
+                "X.O/.X./...",
+                Player::Player1,
+                PositionError::WrongSide(Player::Player1),
+            ),
+            ("XXX/OOO/X..", Player::Player2, PositionError::BothWon),
+            ("XXX/OO./O..", Player::Player1, PositionError::PlayedAfterWin),
+            ("XXX./OO.O/XXX./.OO.:3", Player::Player2, PositionError::PlayedAfterWin),
+            ("XX./OOO/XX.", Player::Player2, PositionError::PlayedAfterWin),
+            // Three in a row would remain whichever cross came last
+            (
+                "XXXXXX./O.O.O../.O.O...:3",
+                Player::Player2,
+                PositionError::PlayedAfterWin,
+            ),
+        ];
+        for (text, to_move, error) in errors {
+            assert_eq!(
+                Game::from_position(Opponent::Human, position(text), to_move).unwrap_err(),
+                error
+            );
+        }
+        // Filling the gap in XX.XX makes five in a row at once
+        assert!(position("XXXXX/OO.OO/.....:3").validate(Player::Player2).is_ok());
+
+        let mut board = Board::new(BoardConfig::connect_four());
+        board.set_cell_force(Position { x: 3, y: 4 }, GameCell::Cross);
+        assert_eq!(
+            board.validate(Player::Player2),
+            Err(PositionError::FloatingPiece(Position { x: 3, y: 4 }))
+        );
+        board.cells.pop();
+        assert_eq!(board.validate(Player::Player2), Err(PositionError::WrongSize));
+
+        // In a wild game only the number of pieces tells whose turn it is
+        let mut board = position("OO./.../...");
+        board.config.wild = true;
+        assert!(board.validate(Player::Player1).is_ok());
+    }
+
+    #[test]
+    fn test_gomoku() {
+        let mut board = Board::new(BoardConfig::gomoku());
+        for x in [3, 4, 6, 7, 8] {
+            board.set_cell_force(Position { x, y: 7 }, GameCell::Cross);
+        }
+        let gap = Position { x: 5, y: 7 };
+        assert_eq!(board.run(gap, (1, 0)), 0);
+        board.set_cell_force(gap, GameCell::Cross);
+        assert_eq!(board.run(gap, (1, 0)), 6);
+        // Six in a row wins in free-style Gomoku but not when the line must be exactly five long
+        assert_eq!(board.state_after(gap), State::Win(GameCell::Cross));
+        assert_eq!(board.get_state(), State::Win(GameCell::Cross));
+        board.config.exact_length = true;
+        assert_eq!(board.state_after(gap), State::Empty);
+        assert_eq!(board.get_state(), State::Empty);
+        board.set_cell_force(Position { x: 3, y: 7 }, GameCell::Empty);
+        assert_eq!(board.state_after(gap), State::Win(GameCell::Cross));
+
+        // Under renju, only the first player is held to exactly five
+        board.config.exact_length = false;
+        board.config.renju = true;
+        board.set_cell_force(Position { x: 3, y: 7 }, GameCell::Circle);
+        board.set_cell_force(Position { x: 9, y: 7 }, GameCell::Circle);
+        for x in [4, 5, 6, 7, 8] {
+            board.set_cell_force(Position { x, y: 7 }, GameCell::Circle);
+        }
+        assert_eq!(board.state_after(gap), State::Win(GameCell::Circle));
+    }
+
+    #[test]
+    fn test_renju() {
+        let config = BoardConfig {
+            renju: true,
+            ..BoardConfig::gomoku()
+        };
+        let mut game = Game::with_config(Opponent::Human, config).unwrap();
+        for (x, y) in [(5, 7), (0, 0), (6, 7), (0, 2), (7, 5), (0, 4), (7, 6), (0, 6)] {
+            game.update(Move::PlaceAt(Position { x, y })).unwrap();
+        }
+        // Crossing two open threes is forbidden to the first player, and the turn is not lost
+        let double_three = Position { x: 7, y: 7 };
+        assert_eq!(
+            game.update(Move::PlaceAt(double_three)),
+            Err(GameError::Forbidden(double_three))
+        );
+        assert_eq!(game.board.get_cell(double_three), Some(&GameCell::Empty));
+        assert_eq!(game.current_player, Player::Player1);
+        game.update(Move::PlaceAt(Position { x: 8, y: 7 })).unwrap();
+        assert_eq!(game.current_player, Player::Player2);
+    }
+
+    #[test]
+    fn test_gomoku_against_computer() {
+        let mut game = Game::with_config(Opponent::Minimax, BoardConfig::gomoku()).unwrap();
+        game.board.set_cell_force(Position { x: 3, y: 0 }, GameCell::Cross);
+        game.board.set_cell_force(Position { x: 4, y: 0 }, GameCell::Cross);
+        game.board.set_cell_force(Position { x: 10, y: 10 }, GameCell::Circle);
+        game.update(Move::PlaceAt(Position { x: 5, y: 0 })).unwrap();
+        // Three crosses open at both ends have to be blocked at once
+        let reply = game.history().last().unwrap().position;
+        assert!(reply == Position { x: 2, y: 0 } || reply == Position { x: 6, y: 0 });
+    }
 }
@@ -0,0 +1,321 @@
+//! Gomoku and other games of `k` in a row on boards too large for the solver.
+//!
+//! Gomoku itself is an ordinary [`Game`](crate::game::Game) on a [`BoardConfig::gomoku`] board;
+//! this module adds the renju restrictions on the first player and a computer player that looks
+//! for threats instead of searching the whole tree. It wins or blocks a win on the spot where it
+//! can, then searches for a win by a series of fours the opponent has to answer (a victory by
+//! continuous fours), and otherwise plays the move that builds or breaks the most promising lines.
+//!
+//! [`BoardConfig::gomoku`]: crate::game::BoardConfig::gomoku
+
+use crate::{
+    error::GameError,
+    game::{Board, DIRECTIONS},
+    update::{GameCell, Position},
+};
+
+/// How many fours in a row a victory by continuous fours may take.
+const FOUR_DEPTH: usize = 8;
+
+/// Empty cells within this many steps of a piece are worth considering.
+const REACH: isize = 2;
+
+/// Whether the first player may not place a piece at `pos` under renju rules: because it would
+/// make an overline, or two fours, or two open threes at once. A move that makes a line of
+/// exactly the winning length is always allowed.
+///
+/// A three only counts when it can become an open four at once; unlike in full renju rules, the
+/// move that would make it an open four is not itself checked for being forbidden.
+pub fn is_forbidden(board: &Board, pos: Position) -> bool {
+    if board.get_cell(pos) != Some(&GameCell::Empty) {
+        return false;
+    }
+    let length = board.config.win_length;
+    let mut board = board.clone();
+    board.set_cell_force(pos, GameCell::Cross);
+    let runs = DIRECTIONS.map(|direction| board.run(pos, direction));
+    if runs.contains(&length) {
+        return false;
+    }
+    if runs.iter().any(|run| *run > length) {
+        return true;
+    }
+    let fours = DIRECTIONS
+        .iter()
+        .filter(|direction| !completions(&board, pos, **direction).is_empty())
+        .count();
+    let threes = DIRECTIONS
+        .iter()
+        .filter(|direction| completions(&board, pos, **direction).is_empty() && is_open_three(&board, pos, **direction))
+        .count();
+    fours >= 2 || threes >= 2
+}
+
+/// The empty cells along `direction` that would give the piece at `pos` a line of exactly the
+/// winning length through `pos`. One of them makes a four, two an open four.
+fn completions(board: &Board, pos: Position, direction: (isize, isize)) -> Vec<Position> {
+    let length = board.config.win_length as isize;
+    let cell = board.cells[pos.y][pos.x];
+    let mut board = board.clone();
+    (-length + 1..length)
+        .filter_map(|steps| board.offset(pos, direction, steps))
+        .filter(|target| board.cells[target.y][target.x] == GameCell::Empty)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter(|target| {
+            board.set_cell_force(*target, cell);
+            let exact = board.run(pos, direction) == length as usize;
+            board.set_cell_force(*target, GameCell::Empty);
+            exact
+        })
+        .collect()
+}
+
+/// Whether one more piece along `direction` can turn the line through `pos` into an open four.
+fn is_open_three(board: &Board, pos: Position, direction: (isize, isize)) -> bool {
+    let length = board.config.win_length as isize;
+    let cell = board.cells[pos.y][pos.x];
+    let mut board = board.clone();
+    (-length + 2..length - 1)
+        .filter_map(|steps| board.offset(pos, direction, steps))
+        .filter(|target| board.cells[target.y][target.x] == GameCell::Empty)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .any(|target| {
+            board.set_cell_force(target, cell);
+            let open = completions(&board, pos, direction).len() >= 2;
+            board.set_cell_force(target, GameCell::Empty);
+            open
+        })
+}
+
+/// The move the threat player picks for `cell` on `board`.
+pub fn best_move(board: &Board, cell: GameCell) -> Result<Position, GameError> {
+    let candidates = candidates(board, cell);
+    if candidates.is_empty() {
+        return Err(GameError::NoMovesAvailable);
+    }
+    let mut board = board.clone();
+    for target in [cell, cell.opposite()] {
+        if let Some(pos) = candidates.iter().find(|pos| wins_with(&mut board, **pos, target)) {
+            return Ok(*pos);
+        }
+    }
+    if let Some(pos) = fours_to_win(&mut board, cell, FOUR_DEPTH) {
+        return Ok(pos);
+    }
+    Ok(candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|pos| score(&mut board, *pos, cell))
+        .unwrap())
+}
+
+/// The empty cells near the pieces already placed, or the centre of an empty board, leaving out
+/// any that are forbidden to `cell`.
+fn candidates(board: &Board, cell: GameCell) -> Vec<Position> {
+    if board.moves() == 0 {
+        let centre = Position {
+            x: board.config.width / 2,
+            y: board.config.height / 2,
+        };
+        return board
+            .available_moves()
+            .into_iter()
+            .filter(|pos| *pos == centre)
+            .collect();
+    }
+    board
+        .available_moves()
+        .into_iter()
+        .filter(|pos| {
+            (-REACH..=REACH).any(|dy| {
+                (-REACH..=REACH).any(|dx| {
+                    board
+                        .offset(*pos, (dx, dy), 1)
+                        .is_some_and(|near| board.cells[near.y][near.x] != GameCell::Empty)
+                })
+            })
+        })
+        .filter(|pos| !(board.config.renju && cell == GameCell::Cross && is_forbidden(board, *pos)))
+        .collect()
+}
+
+/// Whether placing `cell` at `pos` completes a winning line.
+fn wins_with(board: &mut Board, pos: Position, cell: GameCell) -> bool {
+    board.set_cell_force(pos, cell);
+    let wins = board.completes_line(pos);
+    board.set_cell_force(pos, GameCell::Empty);
+    wins
+}
+
+/// The first move of a series of fours that wins for `cell` however the opponent answers, looking
+/// at most `depth` fours ahead. Each four leaves the opponent a single cell to block; two at once
+/// cannot both be blocked.
+fn fours_to_win(board: &mut Board, cell: GameCell, depth: usize) -> Option<Position> {
+    if depth == 0 {
+        return None;
+    }
+    for pos in candidates(board, cell) {
+        board.set_cell_force(pos, cell);
+        let mut blocks = Vec::new();
+        for block in DIRECTIONS
+            .iter()
+            .flat_map(|direction| completions(board, pos, *direction))
+        {
+            if !blocks.contains(&block) {
+                blocks.push(block);
+            }
+        }
+        let wins = match blocks[..] {
+            [] => false,
+            [block] => {
+                // The block must not win for the opponent or leave them a four of their own
+                board.set_cell_force(block, cell.opposite());
+                let quiet = !board.completes_line(block)
+                    && DIRECTIONS
+                        .iter()
+                        .all(|direction| completions(board, block, *direction).is_empty());
+                let wins = quiet && fours_to_win(board, cell, depth - 1).is_some();
+                board.set_cell_force(block, GameCell::Empty);
+                wins
+            }
+            _ => true,
+        };
+        board.set_cell_force(pos, GameCell::Empty);
+        if wins {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+/// How much placing a piece at `pos` is worth to `cell`: the lines it would build for `cell`,
+/// plus a little less for the lines of the opponent's it would cut.
+fn score(board: &mut Board, pos: Position, cell: GameCell) -> i64 {
+    let attack = line_score(board, pos, cell);
+    let defence = line_score(board, pos, cell.opposite());
+    attack * 10 + defence * 9
+}
+
+/// The value of the runs a piece of `cell` at `pos` would be part of: longer runs are worth far
+/// more, and runs blocked at one end half as much as open ones. Runs blocked at both ends are
+/// worthless unless they already win.
+fn line_score(board: &mut Board, pos: Position, cell: GameCell) -> i64 {
+    board.set_cell_force(pos, cell);
+    let length = board.config.win_length;
+    let score = DIRECTIONS
+        .iter()
+        .map(|direction| {
+            let run = board.run(pos, *direction);
+            if run >= length {
+                return 1_000_000_000;
+            }
+            let open = [1, -1]
+                .iter()
+                .filter(|sign| {
+                    let beyond = (1..)
+                        .map(|steps| board.offset(pos, *direction, *sign * steps))
+                        .find(|next| next.is_none_or(|next| board.cells[next.y][next.x] != cell))
+                        .flatten();
+                    beyond.is_some_and(|beyond| board.cells[beyond.y][beyond.x] == GameCell::Empty)
+                })
+                .count();
+            match open {
+                0 => 0,
+                open => 10_i64.pow(run as u32) * open as i64,
+            }
+        })
+        .sum();
+    board.set_cell_force(pos, GameCell::Empty);
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BoardConfig;
+
+    fn place(board: &mut Board, cell: GameCell, cells: &[(usize, usize)]) {
+        for (x, y) in cells {
+            board.set_cell_force(Position { x: *x, y: *y }, cell);
+        }
+    }
+
+    #[test]
+    fn test_forbidden_moves() {
+        let config = BoardConfig {
+            renju: true,
+            ..BoardConfig::gomoku()
+        };
+        let mut board = Board::new(config);
+        // Two open threes crossing at (7, 7)
+        place(&mut board, GameCell::Cross, &[(5, 7), (6, 7), (7, 5), (7, 6)]);
+        assert!(is_forbidden(&board, Position { x: 7, y: 7 }));
+        assert!(!is_forbidden(&board, Position { x: 4, y: 7 }));
+
+        // Blocking one of them leaves a single open three, which is allowed
+        place(&mut board, GameCell::Circle, &[(4, 7)]);
+        assert!(!is_forbidden(&board, Position { x: 7, y: 7 }));
+
+        // Six in a row is forbidden, five is not even when it also makes an overline elsewhere
+        let mut board = Board::new(config);
+        place(&mut board, GameCell::Cross, &[(0, 0), (1, 0), (2, 0), (4, 0), (5, 0)]);
+        assert!(is_forbidden(&board, Position { x: 3, y: 0 }));
+        place(&mut board, GameCell::Cross, &[(3, 1), (3, 2), (3, 3), (3, 4)]);
+        assert!(!is_forbidden(&board, Position { x: 3, y: 0 }));
+
+        // Two fours at once
+        let mut board = Board::new(config);
+        place(
+            &mut board,
+            GameCell::Cross,
+            &[(1, 1), (2, 1), (3, 1), (4, 4), (4, 3), (4, 2)],
+        );
+        place(&mut board, GameCell::Circle, &[(0, 1), (4, 5)]);
+        assert!(is_forbidden(&board, Position { x: 4, y: 1 }));
+    }
+
+    #[test]
+    fn test_takes_win_and_blocks() {
+        let mut board = Board::new(BoardConfig::gomoku());
+        place(&mut board, GameCell::Cross, &[(3, 3), (4, 3), (5, 3), (6, 3)]);
+        place(&mut board, GameCell::Circle, &[(2, 3), (3, 4), (4, 4), (5, 4), (6, 4)]);
+        board.set_cell_force(Position { x: 2, y: 4 }, GameCell::Cross);
+        assert_eq!(best_move(&board, GameCell::Circle), Ok(Position { x: 7, y: 4 }));
+
+        board.set_cell_force(Position { x: 7, y: 4 }, GameCell::Cross);
+        assert_eq!(best_move(&board, GameCell::Circle), Ok(Position { x: 7, y: 3 }));
+    }
+
+    #[test]
+    fn test_wins_with_fours() {
+        let mut board = Board::new(BoardConfig::gomoku());
+        // No single move wins, but (8, 5) makes two fours at once
+        place(
+            &mut board,
+            GameCell::Cross,
+            &[(5, 5), (6, 5), (7, 5), (8, 6), (8, 7), (8, 8)],
+        );
+        place(
+            &mut board,
+            GameCell::Circle,
+            &[(4, 5), (8, 9), (0, 0), (1, 0), (0, 1), (1, 1)],
+        );
+        let mut search = board.clone();
+        let first = fours_to_win(&mut search, GameCell::Cross, FOUR_DEPTH);
+        assert!(first.is_some());
+        assert_eq!(search, board);
+        assert_eq!(best_move(&board, GameCell::Cross), Ok(first.unwrap()));
+    }
+
+    #[test]
+    fn test_opening() {
+        let board = Board::new(BoardConfig::gomoku());
+        assert_eq!(best_move(&board, GameCell::Cross), Ok(Position { x: 7, y: 7 }));
+        let mut board = board;
+        board.set_cell_force(Position { x: 7, y: 7 }, GameCell::Cross);
+        let reply = best_move(&board, GameCell::Circle).unwrap();
+        assert!(reply.x.abs_diff(7) <= 1 && reply.y.abs_diff(7) <= 1);
+    }
+}
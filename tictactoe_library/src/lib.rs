@@ -1,5 +1,19 @@
+pub mod bitboard;
+pub mod dot;
+pub mod error;
 pub mod game;
+pub mod gomoku;
+pub mod mcts;
+pub mod notation;
+pub mod numerical;
 pub mod player;
+pub mod qubic;
+pub mod record;
+pub mod search;
+pub mod solver;
+pub mod tablebase;
+pub mod tree;
+pub mod ultimate;
 pub mod update;
 
 mod client;
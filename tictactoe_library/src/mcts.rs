@@ -0,0 +1,231 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    error::GameError,
+    game::Board,
+    player::Strategy,
+    search::{Searchable, Turn},
+    solver::Outcome,
+    update::{GameCell, Position},
+};
+
+/// A node of the search tree. `reward` is counted for the player who made the move leading here.
+struct Node<A> {
+    parent: Option<usize>,
+    action: Option<A>,
+    children: Vec<usize>,
+    untried: Vec<A>,
+    visits: f64,
+    reward: f64,
+}
+
+/// Monte Carlo Tree Search with the UCT selection rule.
+///
+/// Each iteration walks down the tree picking the child with the best upper confidence bound,
+/// expands one untried move, plays the game out at random and credits the result back up the
+/// path. The move visited most often is played. Given the same seed, the same moves are chosen.
+pub struct Mcts {
+    iterations: usize,
+    exploration: f64,
+    rng: StdRng,
+}
+
+impl Mcts {
+    pub fn new(iterations: usize, exploration: f64) -> Mcts {
+        Mcts {
+            iterations,
+            exploration,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn with_seed(iterations: usize, exploration: f64, seed: u64) -> Mcts {
+        Mcts {
+            iterations,
+            exploration,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The most promising move for the side to move in `root`, or `None` if the game is over.
+    ///
+    /// Random playouts are slow to notice a win or loss one move away, so those are settled first:
+    /// a move that wins on the spot is played straight away, and moves that lose on the spot or
+    /// leave the opponent such a win are only searched if every move does.
+    pub fn search<S: Searchable>(&mut self, root: &S) -> Option<S::Action> {
+        let actions = root.actions();
+        if actions.len() <= 1 {
+            return actions.first().copied();
+        }
+        let mut safe = Vec::new();
+        for action in &actions {
+            let mut state = root.clone();
+            state.play(*action);
+            match state.outcome() {
+                Some(Outcome::Loss) => return Some(*action),
+                Some(Outcome::Win) => continue,
+                Some(Outcome::Draw) => {
+                    safe.push(*action);
+                    continue;
+                }
+                None => {}
+            }
+            let threatened = state.actions().into_iter().any(|reply| {
+                let mut state = state.clone();
+                state.play(reply);
+                state.outcome() == Some(Outcome::Loss)
+            });
+            if !threatened {
+                safe.push(*action);
+            }
+        }
+        let actions = if safe.is_empty() { actions } else { safe };
+        if actions.len() == 1 {
+            return actions.first().copied();
+        }
+        let mut tree = vec![Node {
+            parent: None,
+            action: None,
+            children: Vec::new(),
+            untried: actions,
+            visits: 0.0,
+            reward: 0.0,
+        }];
+
+        for _ in 0..self.iterations {
+            let mut state = root.clone();
+            let mut node = 0;
+
+            // Selection
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select(&tree, node);
+                state.play(tree[node].action.unwrap());
+            }
+
+            // Expansion
+            if !tree[node].untried.is_empty() {
+                let index = self.rng.gen_range(0..tree[node].untried.len());
+                let action = tree[node].untried.swap_remove(index);
+                state.play(action);
+                tree.push(Node {
+                    parent: Some(node),
+                    action: Some(action),
+                    children: Vec::new(),
+                    untried: state.actions(),
+                    visits: 0.0,
+                    reward: 0.0,
+                });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // Simulation, scored for the player who moved into `node`
+            let mut reward = self.playout(state);
+
+            // Backpropagation
+            let mut current = Some(node);
+            while let Some(index) = current {
+                tree[index].visits += 1.0;
+                tree[index].reward += reward;
+                reward = 1.0 - reward;
+                current = tree[index].parent;
+            }
+        }
+
+        tree[0]
+            .children
+            .iter()
+            .rev()
+            .max_by(|a, b| tree[**a].visits.total_cmp(&tree[**b].visits))
+            .and_then(|child| tree[*child].action)
+    }
+
+    fn select<A>(&self, tree: &[Node<A>], node: usize) -> usize {
+        let log_visits = tree[node].visits.ln();
+        let uct = |child: usize| {
+            let child = &tree[child];
+            child.reward / child.visits + self.exploration * (log_visits / child.visits).sqrt()
+        };
+        *tree[node]
+            .children
+            .iter()
+            .rev()
+            .max_by(|a, b| uct(**a).total_cmp(&uct(**b)))
+            .unwrap()
+    }
+
+    /// Plays random moves until the game ends. Returns 1 if the player who moved into `state` won,
+    /// 0 if they lost and 0.5 for a draw.
+    fn playout<S: Searchable>(&mut self, mut state: S) -> f64 {
+        let mut plies = 0;
+        let outcome = loop {
+            if let Some(outcome) = state.outcome() {
+                break outcome;
+            }
+            let actions = state.actions();
+            state.play(actions[self.rng.gen_range(0..actions.len())]);
+            plies += 1;
+        };
+        // `outcome` is for the side to move at the end, which is the opponent of the player who
+        // moved into `state` after an even number of plies
+        let reward = match outcome {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        };
+        if plies % 2 == 0 {
+            1.0 - reward
+        } else {
+            reward
+        }
+    }
+}
+
+impl Strategy for Mcts {
+    fn choose(&mut self, board: &Board, cell: GameCell) -> Result<Position, GameError> {
+        self.search(&Turn::new(board.clone(), cell))
+            .ok_or(GameError::NoMovesAvailable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardConfig, State};
+
+    #[test]
+    fn test_takes_win_and_blocks() {
+        let mut mcts = Mcts::with_seed(2000, 1.4, 7);
+        let mut board = Board::default();
+        board.set_cell_force(Position { x: 0, y: 0 }, GameCell::Cross);
+        board.set_cell_force(Position { x: 1, y: 0 }, GameCell::Cross);
+        board.set_cell_force(Position { x: 1, y: 1 }, GameCell::Circle);
+        assert_eq!(mcts.choose(&board, GameCell::Circle), Ok(Position { x: 2, y: 0 }));
+
+        board.set_cell_force(Position { x: 0, y: 2 }, GameCell::Circle);
+        assert_eq!(mcts.choose(&board, GameCell::Cross), Ok(Position { x: 2, y: 0 }));
+    }
+
+    #[test]
+    fn test_seeded_search_is_deterministic() {
+        let board = Board::new(BoardConfig::new(5, 5, 4).unwrap());
+        let first = Mcts::with_seed(300, 1.4, 42).choose(&board, GameCell::Cross);
+        let second = Mcts::with_seed(300, 1.4, 42).choose(&board, GameCell::Cross);
+        assert_eq!(first, second);
+        assert!(board.available_moves().contains(&first.unwrap()));
+    }
+
+    #[test]
+    fn test_self_play_draws() {
+        let mut mcts = Mcts::with_seed(3000, 1.4, 1);
+        let mut board = Board::default();
+        let mut cell = GameCell::Cross;
+        while board.get_state() == State::Empty {
+            let pos = mcts.choose(&board, cell).unwrap();
+            board.set_cell(pos, cell).unwrap();
+            cell = cell.opposite();
+        }
+        assert_eq!(board.get_state(), State::Draw);
+    }
+}
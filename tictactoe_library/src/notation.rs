@@ -0,0 +1,309 @@
+//! A compact text notation for positions.
+//!
+//! A board is written row by row from the top, rows separated by `/`, with `X` and `O` for the
+//! pieces and `.` for empty cells. If the win length is not the default for the board's size
+//! (three, or the shorter side if that is smaller) it follows the rows after a `:`. Rules other
+//! than the classic ones come last, each after a `+`. A game adds the side to move, `x` or `o`,
+//! after a space:
+//!
+//! ```text
+//! X.O/.X./..O x
+//! ..../.XO./..../.... x
+//! ...../...../...../...../.....:4 x
+//! X.O/.X./...+misere o
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    error::NotationError,
+    game::{Board, BoardConfig, Game, Player, Role},
+    update::{GameCell, Opponent},
+};
+
+/// The win length assumed when a board's notation does not give one.
+fn default_win_length(width: usize, height: usize) -> usize {
+    width.min(height).min(3)
+}
+
+/// The names of the rules `config` plays by, in the order they are written.
+fn rule_names(config: &BoardConfig) -> Vec<&'static str> {
+    [
+        (config.misere, "misere"),
+        (config.gravity, "gravity"),
+        (config.wild, "wild"),
+        // Order and Chaos is named after the role Player 1 plays
+        (config.order_and_chaos == Some(Role::Order), "order"),
+        (config.order_and_chaos == Some(Role::Chaos), "chaos"),
+        (config.exact_length, "exact"),
+        (config.renju, "renju"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect()
+}
+
+/// Turns on the rule called `name` in `config`, or returns `false` if there is no such rule.
+fn set_rule(config: &mut BoardConfig, name: &str) -> bool {
+    match name {
+        "misere" => config.misere = true,
+        "gravity" => config.gravity = true,
+        "wild" => config.wild = true,
+        "order" => config.order_and_chaos = Some(Role::Order),
+        "chaos" => config.order_and_chaos = Some(Role::Chaos),
+        "exact" => config.exact_length = true,
+        "renju" => config.renju = true,
+        _ => return false,
+    }
+    true
+}
+
+fn cell_char(cell: GameCell) -> char {
+    match cell {
+        GameCell::Empty => '.',
+        GameCell::Cross => 'X',
+        GameCell::Circle => 'O',
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = self
+            .cells
+            .iter()
+            .map(|row| row.iter().map(|cell| cell_char(*cell)).collect::<String>())
+            .collect::<Vec<_>>();
+        write!(f, "{}", rows.join("/"))?;
+        if self.config.win_length != default_win_length(self.config.width, self.config.height) {
+            write!(f, ":{}", self.config.win_length)?;
+        }
+        for name in rule_names(&self.config) {
+            write!(f, "+{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Board, NotationError> {
+        let mut rules = s.split('+');
+        let s = rules.next().unwrap_or_default();
+        let (rows, win_length) = match s.split_once(':') {
+            Some((rows, win_length)) => (rows, Some(win_length)),
+            None => (s, None),
+        };
+        if rows.is_empty() {
+            return Err(NotationError::Empty);
+        }
+        let cells = rows
+            .split('/')
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        'X' => Ok(GameCell::Cross),
+                        'O' => Ok(GameCell::Circle),
+                        '.' => Ok(GameCell::Empty),
+                        c => Err(NotationError::InvalidCell(c)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = cells[0].len();
+        if width == 0 {
+            return Err(NotationError::Empty);
+        }
+        if let Some((row, found)) = cells
+            .iter()
+            .map(|row| row.len())
+            .enumerate()
+            .find(|(_, len)| *len != width)
+        {
+            return Err(NotationError::RaggedRow {
+                row: row + 1,
+                expected: width,
+                found,
+            });
+        }
+
+        let height = cells.len();
+        let length = match win_length {
+            Some(text) => text.parse::<usize>().ok(),
+            None => Some(default_win_length(width, height)),
+        };
+        let mut config = length
+            .and_then(|length| BoardConfig::new(width, height, length).ok())
+            .ok_or_else(|| NotationError::InvalidWinLength(win_length.unwrap_or_default().to_string()))?;
+        for rule in rules {
+            if !set_rule(&mut config, rule) {
+                return Err(NotationError::InvalidRule(rule.to_string()));
+            }
+        }
+        Ok(Board { cells, config })
+    }
+}
+
+/// Writes the board and the side to move, e.g. `X.O/.X./..O x`. Once the game is over the
+/// current player is the one who moved last, so the other side is written.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let to_move = if self.is_over() {
+            self.current_player.next()
+        } else {
+            self.current_player
+        };
+        let side = match to_move {
+            Player::Player1 => 'x',
+            Player::Player2 => 'o',
+        };
+        write!(f, "{} {}", self.board, side)
+    }
+}
+
+/// Reads a position written as by [`Game`]'s `Display`. The game is between two humans and is
+/// over if the board already holds a line or is full. Positions no game could have reached are
+/// refused, as [`Game::from_position`] does.
+impl FromStr for Game {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Game, NotationError> {
+        let mut fields = s.split_whitespace();
+        let board = fields.next().ok_or(NotationError::Empty)?.parse::<Board>()?;
+        let player = match fields.next() {
+            Some("x") => Player::Player1,
+            Some("o") => Player::Player2,
+            Some(side) => return Err(NotationError::InvalidSide(side.to_string())),
+            None => return Err(NotationError::MissingSide),
+        };
+        if let Some(rest) = fields.next() {
+            return Err(NotationError::TrailingInput(rest.to_string()));
+        }
+        Game::from_position(Opponent::Human, board, player).map_err(NotationError::Unreachable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::PositionError, update::Position};
+
+    #[test]
+    fn test_round_trip() {
+        for text in [
+            "X.O/.X./..O x",
+            ".../.../... x",
+            "XOX/XOO/OXX o",
+            "XXX/OO./... o",
+            "..../.XO./..../.... x",
+            "...../...../...../...../.....:4 x",
+            "......./......./......./......./......./...X...:4+gravity o",
+        ] {
+            let game = text.parse::<Game>().unwrap();
+            assert_eq!(game.to_string(), text);
+            assert_eq!(game.board.to_string().parse::<Board>().unwrap(), game.board);
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let misere = BoardConfig {
+            misere: true,
+            ..BoardConfig::default()
+        };
+        let board = Board::new(misere);
+        assert_eq!(board.to_string(), ".../.../...+misere");
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        // Completing a line loses, so Player 2 has won
+        let game = "XXX/OO./...+misere o".parse::<Game>().unwrap();
+        assert_eq!(game.winner, Some(Player::Player2));
+
+        let board = Board::new(BoardConfig::connect_four());
+        assert_eq!(
+            board.to_string(),
+            "......./......./......./......./......./.......:4+gravity"
+        );
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+
+        // Rules combine, in a fixed order
+        let board = "OX./.../...+wild+misere".parse::<Board>().unwrap();
+        assert!(board.config.wild && board.config.misere);
+        assert_eq!(board.to_string(), "OX./.../...+misere+wild");
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+
+        for (role, name) in [(Role::Order, "order"), (Role::Chaos, "chaos")] {
+            let board = Board::new(BoardConfig::order_and_chaos(role));
+            assert_eq!(
+                board.to_string(),
+                format!("....../....../....../....../....../......:5+{}", name)
+            );
+            assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        }
+
+        let renju = BoardConfig {
+            exact_length: true,
+            renju: true,
+            ..BoardConfig::gomoku()
+        };
+        let board = Board::new(renju);
+        assert!(board.to_string().ends_with("...............:5+exact+renju"));
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+    }
+
+    #[test]
+    fn test_parse() {
+        let game = "X.O/.X./... o".parse::<Game>().unwrap();
+        assert_eq!(game.current_player, Player::Player2);
+        assert_eq!(game.board.get_cell(Position { x: 2, y: 0 }), Some(&GameCell::Circle));
+        assert_eq!(game.board.config, BoardConfig::default());
+        assert!(!game.is_over());
+
+        let game = "XXX/OO./... o".parse::<Game>().unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.winner, Some(Player::Player1));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!("".parse::<Game>().unwrap_err(), NotationError::Empty);
+        assert_eq!(
+            "X.O/.X x".parse::<Game>().unwrap_err(),
+            NotationError::RaggedRow {
+                row: 2,
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            "X.O/.L./... x".parse::<Game>().unwrap_err(),
+            NotationError::InvalidCell('L')
+        );
+        assert_eq!("X.O/.X./...".parse::<Game>().unwrap_err(), NotationError::MissingSide);
+        assert_eq!(
+            "X.O/.X./... X".parse::<Game>().unwrap_err(),
+            NotationError::InvalidSide("X".to_string())
+        );
+        assert_eq!(
+            "X.O/.X./...:9 x".parse::<Game>().unwrap_err(),
+            NotationError::InvalidWinLength("9".to_string())
+        );
+        assert_eq!(
+            "X.O/.X./...+sideways x".parse::<Game>().unwrap_err(),
+            NotationError::InvalidRule("sideways".to_string())
+        );
+        assert_eq!(
+            "X.O/.X./... x 3".parse::<Game>().unwrap_err(),
+            NotationError::TrailingInput("3".to_string())
+        );
+        // Well formed, but no game fills the board with crosses
+        assert_eq!(
+            "XXX/XXX/XXX o".parse::<Game>().unwrap_err(),
+            NotationError::Unreachable(PositionError::PieceCount { crosses: 9, circles: 0 })
+        );
+        assert_eq!(
+            "X.O/.X./... x".parse::<Game>().unwrap_err(),
+            NotationError::Unreachable(PositionError::WrongSide(Player::Player1))
+        );
+    }
+}
@@ -0,0 +1,665 @@
+//! Numerical tic-tac-toe: the first player places the odd numbers 1 to 9 and the second player the
+//! even numbers 2 to 8, each number at most once. Whoever completes a line of three numbers,
+//! whatever their owners, adding up to 15 wins.
+//!
+//! The game is small enough to solve outright, so the computer plays it with an exact search.
+
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::GameError,
+    game::{BoardConfig, Player, State, Transform},
+    solver::{Outcome, Value},
+    update::{Difficulty, Move, Opponent, Position},
+};
+
+/// Cells along each side of the board.
+pub const SIZE: usize = 3;
+
+/// The sum a line has to reach to win.
+pub const TARGET: u8 = 15;
+
+/// The largest number that can be placed.
+const LARGEST: u8 = 9;
+
+/// Every row, column and diagonal, as indices in row-major order.
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// Score of a won position before subtracting the number of numbers placed by the time it is won.
+const WIN: i32 = 100;
+
+/// A cell of a numerical board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NumberCell {
+    #[default]
+    Empty,
+    Number(u8),
+}
+
+impl NumberCell {
+    pub fn number(&self) -> Option<u8> {
+        match self {
+            NumberCell::Empty => None,
+            NumberCell::Number(number) => Some(*number),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self {
+            NumberCell::Empty => String::from("L"),
+            NumberCell::Number(number) => number.to_string(),
+        }
+    }
+}
+
+/// The numbers `player` starts with: the odd ones for Player 1 and the even ones for Player 2.
+pub fn numbers(player: Player) -> impl Iterator<Item = u8> {
+    let first = match player {
+        Player::Player1 => 1,
+        Player::Player2 => 2,
+    };
+    (first..=LARGEST).step_by(2)
+}
+
+/// The player who owns `number`, if it is one of the numbers in the game.
+pub fn owner(number: u8) -> Option<Player> {
+    match number {
+        1..=LARGEST if number % 2 == 1 => Some(Player::Player1),
+        1..=LARGEST => Some(Player::Player2),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NumericalBoard {
+    pub cells: [[NumberCell; SIZE]; SIZE],
+}
+
+impl Default for NumericalBoard {
+    fn default() -> NumericalBoard {
+        NumericalBoard {
+            cells: [[NumberCell::Empty; SIZE]; SIZE],
+        }
+    }
+}
+
+impl NumericalBoard {
+    pub fn get_cell(&self, pos: Position) -> Option<&NumberCell> {
+        self.cells.get(pos.y)?.get(pos.x)
+    }
+
+    /// The number of numbers placed so far.
+    pub fn moves(&self) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| **cell != NumberCell::Empty)
+            .count()
+    }
+
+    /// The player to move. Player 1 starts and the players take turns, so this follows from the
+    /// number of numbers on the board.
+    pub fn current_player(&self) -> Player {
+        if self.moves() % 2 == 0 {
+            Player::Player1
+        } else {
+            Player::Player2
+        }
+    }
+
+    /// The numbers `player` has not placed yet, smallest first.
+    pub fn inventory(&self, player: Player) -> Vec<u8> {
+        numbers(player)
+            .filter(|number| !self.cells.iter().flatten().any(|cell| cell.number() == Some(*number)))
+            .collect()
+    }
+
+    /// Every empty cell paired with every number the player to move has left.
+    pub fn available_moves(&self) -> Vec<(Position, u8)> {
+        if self.get_state() != State::Empty {
+            return Vec::new();
+        }
+        let inventory = self.inventory(self.current_player());
+        let mut moves = Vec::new();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if self.cells[y][x] == NumberCell::Empty {
+                    moves.extend(inventory.iter().map(|number| (Position { x, y }, *number)));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Places `number` at `pos` for the player to move, who has to own it and not have used it yet.
+    pub fn set_cell(&mut self, pos: Position, number: u8) -> Result<(), GameError> {
+        let cell = *self.get_cell(pos).ok_or(GameError::OutOfBounds(pos))?;
+        if self.get_state() != State::Empty {
+            return Err(GameError::GameOver);
+        }
+        if cell != NumberCell::Empty {
+            return Err(GameError::CellOccupied(pos));
+        }
+        if !self.inventory(self.current_player()).contains(&number) {
+            return Err(GameError::WrongNumber(number));
+        }
+        self.cells[pos.y][pos.x] = NumberCell::Number(number);
+        Ok(())
+    }
+
+    /// Won by the player who completed a line adding up to [`TARGET`], given as their piece.
+    /// Drawn once every number has been placed without one.
+    pub fn get_state(&self) -> State {
+        let cells = self.to_array();
+        if LINES.iter().any(|line| is_complete(&cells, line)) {
+            // The player who moved last completed the line
+            State::Win(self.current_player().next().get_cell())
+        } else if self.moves() == SIZE * SIZE {
+            State::Draw
+        } else {
+            State::Empty
+        }
+    }
+
+    /// The cells in row-major order, with 0 for an empty cell.
+    fn to_array(self) -> [u8; SIZE * SIZE] {
+        let mut cells = [0; SIZE * SIZE];
+        for (i, cell) in self.cells.iter().flatten().enumerate() {
+            cells[i] = cell.number().unwrap_or(0);
+        }
+        cells
+    }
+}
+
+fn is_complete(cells: &[u8; SIZE * SIZE], line: &[usize; 3]) -> bool {
+    line.iter().all(|i| cells[*i] != 0) && line.iter().map(|i| cells[*i]).sum::<u8>() == TARGET
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// Solves numerical positions to the end with alpha-beta pruning, remembering every position it
+/// has seen up to symmetry.
+///
+/// Scores are [`WIN`] less the number of numbers on the board once the line is completed, from the
+/// point of view of the player to move, so a position scores the same however it was reached.
+pub struct NumericalSolver {
+    table: HashMap<u64, (i32, Bound)>,
+    /// For each symmetry of the board, the cell every cell moves to
+    symmetries: Vec<[usize; SIZE * SIZE]>,
+}
+
+impl NumericalSolver {
+    pub fn new() -> NumericalSolver {
+        let config = BoardConfig::default();
+        let symmetries = Transform::ALL
+            .iter()
+            .map(|transform| {
+                let mut map = [0; SIZE * SIZE];
+                for (i, target) in map.iter_mut().enumerate() {
+                    let pos = transform.apply(
+                        Position {
+                            x: i % SIZE,
+                            y: i / SIZE,
+                        },
+                        config,
+                    );
+                    *target = pos.y * SIZE + pos.x;
+                }
+                map
+            })
+            .collect();
+        NumericalSolver {
+            table: HashMap::new(),
+            symmetries,
+        }
+    }
+
+    /// The value of `board` for the player to move.
+    pub fn solve(&mut self, board: &NumericalBoard) -> Value {
+        let mut cells = board.to_array();
+        let score = match board.get_state() {
+            State::Win(_) => -(WIN - board.moves() as i32),
+            State::Draw => 0,
+            State::Empty => self.negamax(&mut cells, -WIN, WIN),
+        };
+        to_value(score, &cells)
+    }
+
+    /// The value of every legal move for the player to move.
+    pub fn solve_moves(&mut self, board: &NumericalBoard) -> Vec<((Position, u8), Value)> {
+        let mut cells = board.to_array();
+        board
+            .available_moves()
+            .into_iter()
+            .map(|(pos, number)| {
+                let score = self.play(&mut cells, pos.y * SIZE + pos.x, number, -WIN, WIN);
+                ((pos, number), to_value(score, &cells))
+            })
+            .collect()
+    }
+
+    /// The quickest win, or failing that a draw, or the slowest loss. Ties go to the first move in
+    /// [`NumericalBoard::available_moves`].
+    pub fn best_move(&mut self, board: &NumericalBoard) -> Result<(Position, u8), GameError> {
+        let mut cells = board.to_array();
+        let mut best: Option<((Position, u8), i32)> = None;
+        for (pos, number) in board.available_moves() {
+            let score = self.play(&mut cells, pos.y * SIZE + pos.x, number, -WIN, WIN);
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some(((pos, number), score));
+            }
+        }
+        best.map(|(mov, _)| mov).ok_or(GameError::NoMovesAvailable)
+    }
+
+    /// The score of placing `number` in cell `index` of `cells`, for the player placing it.
+    fn play(&mut self, cells: &mut [u8; SIZE * SIZE], index: usize, number: u8, alpha: i32, beta: i32) -> i32 {
+        cells[index] = number;
+        let score = if completes_line(cells, index) {
+            WIN - placed(cells) as i32
+        } else if placed(cells) == cells.len() {
+            0
+        } else {
+            -self.negamax(cells, -beta, -alpha)
+        };
+        cells[index] = 0;
+        score
+    }
+
+    /// The score of an unfinished position for the player to move.
+    fn negamax(&mut self, cells: &mut [u8; SIZE * SIZE], mut alpha: i32, mut beta: i32) -> i32 {
+        let placed = placed(cells);
+        let first = if placed % 2 == 0 { 1 } else { 2 };
+        let available = (first..=LARGEST)
+            .step_by(2)
+            .filter(|number| !cells.contains(number))
+            .collect::<Vec<_>>();
+        let empty = (0..cells.len()).filter(|i| cells[*i] == 0).collect::<Vec<_>>();
+
+        // Completing a line right away cannot be bettered, so look for one before searching deeper
+        for index in &empty {
+            for number in &available {
+                cells[*index] = *number;
+                let won = completes_line(cells, *index);
+                cells[*index] = 0;
+                if won {
+                    return WIN - (placed + 1) as i32;
+                }
+            }
+        }
+        // Otherwise the soonest this player can complete a line is on their next move
+        beta = beta.min(WIN - (placed + 3) as i32);
+        if alpha >= beta {
+            return beta;
+        }
+
+        let key = self.key(cells);
+        let original = alpha;
+        if let Some((score, bound)) = self.table.get(&key) {
+            match bound {
+                Bound::Exact => return *score,
+                Bound::Lower => alpha = alpha.max(*score),
+                Bound::Upper => beta = beta.min(*score),
+            }
+            if alpha >= beta {
+                return *score;
+            }
+        }
+
+        let mut best = -WIN;
+        'search: for index in &empty {
+            for number in &available {
+                best = best.max(self.play(cells, *index, *number, alpha, beta));
+                alpha = alpha.max(best);
+                if alpha >= beta {
+                    break 'search;
+                }
+            }
+        }
+        let bound = if best <= original {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(key, (best, bound));
+        best
+    }
+
+    /// The smallest packing of `cells` over all symmetries, four bits per cell.
+    fn key(&self, cells: &[u8; SIZE * SIZE]) -> u64 {
+        self.symmetries
+            .iter()
+            .map(|map| (0..cells.len()).fold(0, |key, i| key | (cells[i] as u64) << (4 * map[i])))
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Default for NumericalSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn placed(cells: &[u8; SIZE * SIZE]) -> usize {
+    cells.iter().filter(|cell| **cell != 0).count()
+}
+
+/// Whether the number in cell `index` completes a line through it.
+fn completes_line(cells: &[u8; SIZE * SIZE], index: usize) -> bool {
+    LINES
+        .iter()
+        .any(|line| line.contains(&index) && is_complete(cells, line))
+}
+
+fn to_value(score: i32, cells: &[u8; SIZE * SIZE]) -> Value {
+    let placed = placed(cells);
+    match score {
+        0 => Value {
+            outcome: Outcome::Draw,
+            plies: cells.len() - placed,
+        },
+        score if score > 0 => Value {
+            outcome: Outcome::Win,
+            plies: (WIN - score) as usize - placed,
+        },
+        score => Value {
+            outcome: Outcome::Loss,
+            plies: (WIN + score) as usize - placed,
+        },
+    }
+}
+
+/// The move the computer playing as `opponent` picks for the player to move on `board`.
+///
+/// [`Difficulty::depth`] is not used: the whole game is solved in a fraction of a second, and a
+/// search cut off early has no way to score the positions it stops at. The difficulty only sets
+/// how often the computer blunders.
+pub fn get_move(opponent: Opponent, board: &NumericalBoard) -> Result<(Position, u8), GameError> {
+    let difficulty = match opponent {
+        Opponent::Random => return random_move(board),
+        Opponent::Minimax => Difficulty::Perfect,
+        Opponent::Computer(difficulty) => difficulty,
+        Opponent::Human | Opponent::Online | Opponent::Custom => return Err(GameError::OpponentCannotMove(opponent)),
+    };
+    if rand::thread_rng().gen_bool(difficulty.blunder_chance()) {
+        return random_move(board);
+    }
+    NumericalSolver::new().best_move(board)
+}
+
+fn random_move(board: &NumericalBoard) -> Result<(Position, u8), GameError> {
+    board
+        .available_moves()
+        .choose(&mut rand::thread_rng())
+        .copied()
+        .ok_or(GameError::NoMovesAvailable)
+}
+
+/// A number placed during a game.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NumericalPly {
+    pub player: Player,
+    pub position: Position,
+    pub number: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NumericalState {
+    GameOver(Option<Player>, NumericalBoard),
+    GameInProgress(NumericalBoard, Player, Position),
+}
+
+/// A game of numerical tic-tac-toe, driven the same way as a [`Game`](crate::game::Game) but with
+/// [`Move::PlaceNumber`] and [`Move::PlaceNumberAt`] to place a number.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NumericalGame {
+    pub board: NumericalBoard,
+    pub current_position: Position,
+    pub current_player: Player,
+    pub winner: Option<Player>,
+    pub opponent: Opponent,
+    should_continue: bool,
+    state_changed: bool,
+    #[serde(default)]
+    history: Vec<NumericalPly>,
+}
+
+impl NumericalGame {
+    /// A new game against `opponent`. Only the built-in opponents place numbers, so
+    /// [`Opponent::Custom`] is refused with [`GameError::OpponentCannotMove`].
+    pub fn new(opponent: Opponent) -> Result<NumericalGame, GameError> {
+        if opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(opponent));
+        }
+        Ok(NumericalGame {
+            board: NumericalBoard::default(),
+            current_position: Position { x: 1, y: 1 },
+            current_player: Player::Player1,
+            winner: None,
+            opponent,
+            should_continue: true,
+            state_changed: true,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, mov: Move) -> Result<NumericalState, GameError> {
+        let pos = self.current_position;
+        match mov {
+            Move::Up if pos.y > 0 => self.current_position.y -= 1,
+            Move::Down if pos.y + 1 < SIZE => self.current_position.y += 1,
+            Move::Left if pos.x > 0 => self.current_position.x -= 1,
+            Move::Right if pos.x + 1 < SIZE => self.current_position.x += 1,
+            Move::Up | Move::Down | Move::Left | Move::Right => return Err(GameError::CannotMove(mov)),
+            Move::PlaceNumber(number) => self.play(pos, number)?,
+            Move::PlaceNumberAt(pos, number) => self.play(pos, number)?,
+            // Pieces have no place on a numerical board
+            Move::Place | Move::PlaceAt(_) => return Err(GameError::WrongPiece(self.current_player.get_cell())),
+            Move::PlaceWith(cell) | Move::PlaceAtWith(_, cell) => return Err(GameError::WrongPiece(cell)),
+            Move::LayerUp | Move::LayerDown | Move::PlaceAt3(_) => return Err(GameError::CannotMove(mov)),
+        }
+        self.state_changed = true;
+        Ok(self.get_state().unwrap())
+    }
+
+    /// Places `number` at `pos` for the current player and lets the computer reply if it is its turn.
+    fn play(&mut self, pos: Position, number: u8) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        // A deserialised game can still name a custom opponent, which could not reply
+        if self.opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(self.opponent));
+        }
+        self.board.set_cell(pos, number)?;
+        self.history.push(NumericalPly {
+            player: self.current_player,
+            position: pos,
+            number,
+        });
+        match self.board.get_state() {
+            State::Empty => self.current_player = self.current_player.next(),
+            State::Win(_) => {
+                self.winner = Some(self.current_player);
+                self.should_continue = false;
+            }
+            State::Draw => self.should_continue = false,
+        }
+        self.state_changed = true;
+        let computer = !matches!(self.opponent, Opponent::Human | Opponent::Online);
+        if self.should_continue && computer && self.current_player == Player::Player2 {
+            let (pos, number) = get_move(self.opponent, &self.board)?;
+            return self.play(pos, number);
+        }
+        Ok(())
+    }
+
+    /// The numbers placed so far, in the order they were played.
+    pub fn history(&self) -> &[NumericalPly] {
+        &self.history
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.should_continue
+    }
+
+    pub fn get_state(&mut self) -> Option<NumericalState> {
+        if !self.state_changed {
+            return None;
+        }
+        self.state_changed = false;
+        if self.is_over() {
+            Some(NumericalState::GameOver(self.winner, self.board))
+        } else {
+            Some(NumericalState::GameInProgress(
+                self.board,
+                self.current_player,
+                self.current_position,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rows: [[u8; SIZE]; SIZE]) -> NumericalBoard {
+        let mut board = NumericalBoard::default();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, number) in row.iter().enumerate() {
+                if *number != 0 {
+                    board.cells[y][x] = NumberCell::Number(*number);
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn test_inventories() {
+        let mut board = NumericalBoard::default();
+        assert_eq!(board.inventory(Player::Player1), vec![1, 3, 5, 7, 9]);
+        assert_eq!(board.inventory(Player::Player2), vec![2, 4, 6, 8]);
+        assert_eq!(board.available_moves().len(), 9 * 5);
+        assert_eq!(owner(4), Some(Player::Player2));
+        assert_eq!(owner(10), None);
+
+        board.set_cell(Position { x: 0, y: 0 }, 5).unwrap();
+        assert_eq!(board.current_player(), Player::Player2);
+        assert_eq!(
+            board.set_cell(Position { x: 1, y: 0 }, 3),
+            Err(GameError::WrongNumber(3))
+        );
+        board.set_cell(Position { x: 1, y: 0 }, 2).unwrap();
+        assert_eq!(
+            board.set_cell(Position { x: 2, y: 0 }, 5),
+            Err(GameError::WrongNumber(5))
+        );
+        assert_eq!(
+            board.set_cell(Position { x: 1, y: 0 }, 7),
+            Err(GameError::CellOccupied(Position { x: 1, y: 0 }))
+        );
+        assert_eq!(board.inventory(Player::Player1), vec![1, 3, 7, 9]);
+        assert_eq!(board.inventory(Player::Player2), vec![4, 6, 8]);
+    }
+
+    #[test]
+    fn test_lines() {
+        // Player 2 completes the top row, 5 + 2 + 8, using Player 1's 5 along the way
+        let mut game = NumericalGame::new(Opponent::Human).unwrap();
+        for (x, y, number) in [(0, 0, 5), (1, 0, 2), (0, 2, 1), (2, 2, 6), (1, 1, 9)] {
+            game.update(Move::PlaceNumberAt(Position { x, y }, number)).unwrap();
+        }
+        assert!(!game.is_over());
+        assert_eq!(game.board.get_state(), State::Empty);
+        game.update(Move::PlaceNumberAt(Position { x: 2, y: 0 }, 8)).unwrap();
+        assert_eq!(game.board.get_state(), State::Win(Player::Player2.get_cell()));
+        assert_eq!(game.winner, Some(Player::Player2));
+        assert_eq!(game.history().len(), 6);
+        assert_eq!(
+            game.update(Move::PlaceNumberAt(Position { x: 0, y: 1 }, 3)),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_cursor_and_pieces() {
+        let mut game = NumericalGame::new(Opponent::Human).unwrap();
+        game.update(Move::Up).unwrap();
+        assert_eq!(game.update(Move::Up), Err(GameError::CannotMove(Move::Up)));
+        assert_eq!(
+            game.update(Move::Place),
+            Err(GameError::WrongPiece(Player::Player1.get_cell()))
+        );
+        game.update(Move::PlaceNumber(7)).unwrap();
+        assert_eq!(
+            game.board.get_cell(Position { x: 1, y: 0 }),
+            Some(&NumberCell::Number(7))
+        );
+        assert_eq!(game.current_player, Player::Player2);
+    }
+
+    #[test]
+    fn test_solver() {
+        let mut solver = NumericalSolver::new();
+        // Player 2's 2 and 4 leave the top row 9 short of 15, and Player 1 still holds the 9
+        let threat = board([[2, 4, 0], [1, 0, 0], [3, 0, 0]]);
+        assert_eq!(solver.solve(&threat).outcome, Outcome::Win);
+        let (pos, number) = solver.best_move(&threat).unwrap();
+        let mut played = threat;
+        played.set_cell(pos, number).unwrap();
+        assert_eq!(played.get_state(), State::Win(Player::Player1.get_cell()));
+        assert_eq!(
+            solver.solve(&threat),
+            Value {
+                outcome: Outcome::Win,
+                plies: 1
+            }
+        );
+
+        // The first player wins with perfect play
+        assert_eq!(solver.solve(&NumericalBoard::default()).outcome, Outcome::Win);
+        let values = solver.solve_moves(&NumericalBoard::default());
+        assert_eq!(values.len(), 45);
+        assert!(values.iter().any(|(_, value)| value.outcome == Outcome::Win));
+    }
+
+    #[test]
+    fn test_game_against_computer() {
+        let mut game = NumericalGame::new(Opponent::Minimax).unwrap();
+        while !game.is_over() {
+            let (pos, number) = *game.board.available_moves().choose(&mut rand::thread_rng()).unwrap();
+            game.update(Move::PlaceNumberAt(pos, number)).unwrap();
+            assert!(game.is_over() || game.current_player == Player::Player1);
+        }
+        match game.board.get_state() {
+            State::Win(_) => assert_eq!(game.winner, game.history().last().map(|ply| ply.player)),
+            state => assert_eq!(state, State::Draw),
+        }
+        assert_eq!(
+            NumericalGame::new(Opponent::Custom),
+            Err(GameError::OpponentCannotMove(Opponent::Custom))
+        );
+    }
+}
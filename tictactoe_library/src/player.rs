@@ -1,167 +1,271 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    bitboard::{Layout, MAX_CELLS},
+    error::GameError,
     game::{Board, State},
-    update::{GameCell, Opponent, Position},
+    gomoku,
+    mcts::Mcts,
+    search::WildTurn,
+    solver::{Solver, Value},
+    tablebase,
+    update::{Difficulty, GameCell, Opponent, Position},
 };
 use rand::Rng;
 
-pub fn get_pos(player: Opponent, board: &Board, cell: &GameCell) -> Result<Position, String> {
-    match player {
-        Opponent::Random => random_play(board),
-        Opponent::Minimax => minimax_play(board, cell),
-        Opponent::Human => Err("Player is not allowed to play".to_string()),
-        Opponent::Online => Err("Player is not allowed to play".to_string()),
-        // Opponent::Online(player) => Err("Use async for online player".to_string()),
+/// Chooses moves for a computer-controlled player.
+///
+/// Implement this to plug your own bot into a [`Game`](crate::game::Game) with [`Game::new`](crate::game::Game::new).
+pub trait Strategy: Send {
+    /// Picks an empty cell of `board` to place `cell` in.
+    fn choose(&mut self, board: &Board, cell: GameCell) -> Result<Position, GameError>;
+
+    /// Picks a cell and the piece to place there in a wild game or Order and Chaos, where `cell`
+    /// is this player's own piece. By default it places its own piece where [`Strategy::choose`] would.
+    fn choose_wild(&mut self, board: &Board, cell: GameCell) -> Result<(Position, GameCell), GameError> {
+        Ok((self.choose(board, cell)?, cell))
     }
 }
 
-fn random_play(board: &Board) -> Result<Position, String> {
-    let mut rng = rand::thread_rng();
-    let available_moves = board.available_moves();
-    if available_moves.is_empty() {
-        return Err("No available moves".to_string());
+/// Plays a uniformly random available move.
+pub struct RandomPlayer;
+
+impl Strategy for RandomPlayer {
+    fn choose(&mut self, board: &Board, _cell: GameCell) -> Result<Position, GameError> {
+        random_play(board)
     }
-    let move_index = rng.gen_range(0..available_moves.len());
-    Ok(available_moves[move_index])
-}
 
-fn minimax_play(board: &Board, cell: &GameCell) -> Result<Position, String> {
-    let available_moves = board.available_moves();
-    if available_moves.is_empty() {
-        return Err("No available moves".to_string());
+    fn choose_wild(&mut self, board: &Board, _cell: GameCell) -> Result<(Position, GameCell), GameError> {
+        let cell = if rand::thread_rng().gen_bool(0.5) {
+            GameCell::Cross
+        } else {
+            GameCell::Circle
+        };
+        Ok((random_play(board)?, cell))
     }
-    let best_move = minimax(board, cell);
-    Ok(best_move.unwrap())
 }
 
-fn minimax(board: &Board, cell: &GameCell) -> Result<Position, String> {
-    if board.cells[0][0] == GameCell::Empty {
-        return Ok(Position { x: 0, y: 0 });
+/// Positions with more empty cells than this are searched [`LOOKAHEAD`] plies deep at
+/// [`Difficulty::Perfect`] instead of being solved to the end, and are too large to [`analyse`].
+const SOLVE_LIMIT: usize = 16;
+
+const LOOKAHEAD: usize = 6;
+
+/// Plays the best move found by the [`Solver`], blundering now and then below [`Difficulty::Perfect`].
+///
+/// The solver's transposition table is kept for as long as the player is, so it carries over
+/// between moves when the player is plugged in as a custom [`Strategy`]. The built-in opponents of
+/// a [`Game`](crate::game::Game) start with a fresh one every move.
+pub struct MinimaxPlayer {
+    difficulty: Difficulty,
+    solver: Solver,
+}
+
+impl MinimaxPlayer {
+    pub fn new(difficulty: Difficulty) -> MinimaxPlayer {
+        MinimaxPlayer {
+            difficulty,
+            solver: Solver::with_depth(difficulty.depth()),
+        }
     }
-    let mut best_move = None;
-    let mut best_score = i64::min_value();
-    for m in board.available_moves().iter() {
-        let mut new_board = board.clone();
-        new_board.set_cell_force(*m, *cell);
-        let score = minimax_score(&mut new_board, cell, 3, true, i64::min_value(), i64::max_value(), 3);
-        if score > best_score {
-            best_move = Some(*m);
-            best_score = score;
+
+    /// Limits the search on boards too large to solve.
+    fn set_depth(&mut self, board: &Board) {
+        let empty = board.config.width * board.config.height - board.moves();
+        let depth = match self.difficulty.depth() {
+            None if empty > SOLVE_LIMIT => Some(LOOKAHEAD),
+            depth => depth,
         };
+        self.solver.set_depth(depth);
+    }
+
+    fn blunders(&self) -> bool {
+        rand::thread_rng().gen_bool(self.difficulty.blunder_chance())
     }
-    Ok(best_move.unwrap())
 }
 
-fn evaluate(board: &Board, cell: &GameCell) -> i64 {
-    match board.get_state() {
-        State::Win(c) => {
-            if c == *cell {
-                1000
-            } else {
-                -1000
-            }
+impl Strategy for MinimaxPlayer {
+    fn choose(&mut self, board: &Board, cell: GameCell) -> Result<Position, GameError> {
+        if self.blunders() {
+            return random_play(board);
         }
-        State::Draw => 0,
-        _ => 0,
-    }
-}
-fn minimax_score(
-    board: &mut Board,
-    cell: &GameCell,
-    depth: i64,
-    is_maximizing: bool,
-    mut alpha: i64,
-    mut beta: i64,
-    max_depth: i64,
-) -> i64 {
-    let moves = board.available_moves();
-    if depth == 0 || board.get_state() != State::Empty || moves.is_empty() {
-        return evaluate(board, cell);
-    }
-
-    if is_maximizing {
-        let mut value = i64::min_value();
-        for idx in moves {
-            board.set_cell_force(idx, *cell);
-            let score = minimax_score(
-                &mut board.clone(),
-                &cell.opposite(),
-                depth - 1,
-                false,
-                alpha,
-                beta,
-                max_depth,
-            );
-            if score >= value {
-                value = score;
-            }
-            if score >= alpha {
-                alpha = score;
-            }
-            board.set_cell_force(idx, GameCell::Empty);
-            if beta <= alpha {
-                break;
+        if board.config.width * board.config.height > MAX_CELLS {
+            // Too large for the solver, as Gomoku is, so look for threats instead
+            return gomoku::best_move(board, cell);
+        }
+        if self.difficulty.depth().is_none() {
+            // Classic tic-tac-toe is already solved
+            if let Some(pos) = tablebase::lookup(board, cell).and_then(|entry| entry.best_moves().first().copied()) {
+                return Ok(pos);
             }
         }
-        if value != 0 {
-            return value - (max_depth - depth) as i64;
+        self.set_depth(board);
+        self.solver.best_move(board, cell)
+    }
+
+    fn choose_wild(&mut self, board: &Board, cell: GameCell) -> Result<(Position, GameCell), GameError> {
+        if self.blunders() {
+            return RandomPlayer.choose_wild(board, cell);
         }
-        value
-    } else {
-        let mut value = i64::max_value();
-        for idx in moves {
-            board.set_cell_force(idx, cell.opposite());
-            let score = minimax_score(board, &cell.opposite(), depth - 1, true, alpha, beta, max_depth);
-            if score <= value {
-                value = score;
-            }
-            if score <= beta {
-                beta = score;
-            }
-            board.set_cell_force(idx, GameCell::Empty);
-            if beta <= alpha {
-                break;
-            }
+        if board.config.order_and_chaos.is_some() {
+            // Order and Chaos is too large to solve and the roles make a poor fit for the
+            // solver's heuristic, so it is searched with Mcts
+            return Mcts::new(self.difficulty.iterations(), 1.4)
+                .search(&WildTurn::new(board.clone(), cell))
+                .ok_or(GameError::NoMovesAvailable);
         }
+        self.set_depth(board);
+        self.solver.best_wild_move(board)
+    }
+}
+
+/// A strategy that can be cloned along with the game it plays in; clones share the same bot.
+#[derive(Clone)]
+pub struct SharedStrategy(Arc<Mutex<Box<dyn Strategy>>>);
+
+impl SharedStrategy {
+    pub fn new(strategy: Box<dyn Strategy>) -> SharedStrategy {
+        SharedStrategy(Arc::new(Mutex::new(strategy)))
+    }
+
+    pub fn choose(&self, board: &Board, cell: GameCell) -> Result<Position, GameError> {
+        let mut strategy = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        strategy.choose(board, cell)
+    }
+
+    pub fn choose_wild(&self, board: &Board, cell: GameCell) -> Result<(Position, GameCell), GameError> {
+        let mut strategy = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        strategy.choose_wild(board, cell)
+    }
+}
+
+impl fmt::Debug for SharedStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedStrategy")
+    }
+}
+
+impl PartialEq for SharedStrategy {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The built-in strategy behind `opponent`, if the computer plays for it.
+pub fn strategy(opponent: Opponent) -> Option<Box<dyn Strategy>> {
+    match opponent {
+        Opponent::Random => Some(Box::new(RandomPlayer)),
+        Opponent::Minimax => Some(Box::new(MinimaxPlayer::new(Difficulty::Perfect))),
+        Opponent::Computer(difficulty) => Some(Box::new(MinimaxPlayer::new(difficulty))),
+        Opponent::Human | Opponent::Online | Opponent::Custom => None,
+    }
+}
+
+pub fn get_pos(player: Opponent, board: &Board, cell: &GameCell) -> Result<Position, GameError> {
+    match strategy(player) {
+        Some(mut strategy) => strategy.choose(board, *cell),
+        None => Err(GameError::OpponentCannotMove(player)),
+    }
+}
+
+/// Like [`get_pos`] for a wild game or Order and Chaos, also picking the piece to place.
+pub fn get_wild_move(player: Opponent, board: &Board, cell: GameCell) -> Result<(Position, GameCell), GameError> {
+    match strategy(player) {
+        Some(mut strategy) => strategy.choose_wild(board, cell),
+        None => Err(GameError::OpponentCannotMove(player)),
+    }
+}
+
+/// One available move and where it leads under perfect play.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MoveAnalysis {
+    pub position: Position,
+    /// The piece placed, which is only ever not the player's own in wild games and Order and Chaos
+    pub cell: GameCell,
+    /// The outcome for the player making the move, counting plies from before it
+    pub value: Value,
+}
+
+impl MoveAnalysis {
+    /// Whether this move gives away a result the best move in `analysis` would have kept: a win
+    /// that becomes a draw or loss, or a draw that becomes a loss.
+    pub fn is_blunder(&self, analysis: &[MoveAnalysis]) -> bool {
+        analysis.iter().any(|other| other.value.outcome > self.value.outcome)
+    }
+}
 
-        if value != 0 {
-            return value + (max_depth - depth) as i64;
+/// Every move available to `cell` on `board` with its solved value, in the order of
+/// [`Board::available_moves`]. In wild games and Order and Chaos each cell is listed once for
+/// each piece.
+///
+/// Classic tic-tac-toe is looked up in the [`tablebase`](crate::tablebase). Other games are
+/// solved to the end, so positions with more than [`SOLVE_LIMIT`] empty cells are refused with
+/// [`GameError::BoardTooLarge`] rather than searched for minutes.
+pub fn analyse(board: &Board, cell: GameCell) -> Result<Vec<MoveAnalysis>, GameError> {
+    let empty = board.config.width * board.config.height - board.moves();
+    if empty > SOLVE_LIMIT || Layout::get(board.config).is_none() {
+        return Err(GameError::BoardTooLarge);
+    }
+    if board.get_state() != State::Empty {
+        return Err(GameError::NoMovesAvailable);
+    }
+    let pieces = if board.config.free_pieces() {
+        vec![GameCell::Cross, GameCell::Circle]
+    } else {
+        vec![cell]
+    };
+    let mut solver = Solver::new();
+    let mut analysis = Vec::new();
+    for position in board.available_moves() {
+        for piece in &pieces {
+            let mut child = board.clone();
+            child.set_cell_force(position, *piece);
+            let value = match tablebase::lookup(&child, cell.opposite()) {
+                Some(entry) => entry.value,
+                None => solver.solve(&child, cell.opposite())?,
+            };
+            analysis.push(MoveAnalysis {
+                position,
+                cell: *piece,
+                value: value.before_move(),
+            });
         }
-        value
-    }
-}
-
-// fn minimax_score(board: &Board, cell: &GameCell, mut alpha: i32, beta: i32) -> i32 {
-//     match board.get_state() {
-//         State::Win(c) => {
-//             if c == *cell {
-//                 1
-//             } else {
-//                 -1
-//             }
-//         }
-//         State::Draw => 0,
-//         State::Empty => {
-//             let mut best_score = i32::min_value();
-//             for m in board.available_moves().iter() {
-//                 let mut new_board = board.clone();
-//                 new_board.set_cell(*m, *cell);
-//                 let score = minimax_score(&new_board, cell, alpha, beta);
-//                 if score > best_score {
-//                     best_score = score;
-//                 };
-//                 if best_score >= beta {
-//                     return best_score;
-//                 }
-//                 alpha = std::cmp::max(alpha, best_score);
-//             }
-//             best_score
-//         }
-//     }
-// }
+    }
+    Ok(analysis)
+}
+
+/// The best move for `cell` on `board` as found by [`analyse`]: the fastest win, otherwise a
+/// draw, otherwise the slowest loss.
+pub fn hint(board: &Board, cell: GameCell) -> Result<MoveAnalysis, GameError> {
+    analyse(board, cell)?
+        .into_iter()
+        .rev()
+        .max_by_key(|analysis| analysis.value)
+        .ok_or(GameError::NoMovesAvailable)
+}
+
+fn random_play(board: &Board) -> Result<Position, GameError> {
+    let mut rng = rand::thread_rng();
+    let available_moves = board.available_moves();
+    if available_moves.is_empty() {
+        return Err(GameError::NoMovesAvailable);
+    }
+    let move_index = rng.gen_range(0..available_moves.len());
+    Ok(available_moves[move_index])
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        game::{BoardConfig, Role},
+        solver::Outcome,
+    };
 
     #[test]
     fn test_add() {
@@ -192,4 +296,150 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_minimax_never_loses() {
+        for minimax in [GameCell::Cross, GameCell::Circle] {
+            for _ in 0..20 {
+                let mut board = Board::default();
+                let mut cell = GameCell::Cross;
+                while board.get_state() == State::Empty {
+                    let opponent = if cell == minimax {
+                        Opponent::Minimax
+                    } else {
+                        Opponent::Random
+                    };
+                    board.set_cell(get_pos(opponent, &board, &cell).unwrap(), cell).unwrap();
+                    cell = cell.opposite();
+                }
+                assert_ne!(board.get_state(), State::Win(minimax.opposite()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let mut board = Board::default();
+        board.set_cell_force(Position { x: 0, y: 0 }, GameCell::Cross);
+        board.set_cell_force(Position { x: 1, y: 0 }, GameCell::Cross);
+        board.set_cell_force(Position { x: 0, y: 1 }, GameCell::Circle);
+        // Looking two plies ahead is enough to block the top row
+        let mov = Solver::with_depth(Difficulty::Medium.depth()).best_move(&board, GameCell::Circle);
+        assert_eq!(mov, Ok(Position { x: 2, y: 0 }));
+
+        for difficulty in [
+            Difficulty::Easy,
+            Difficulty::Medium,
+            Difficulty::Hard,
+            Difficulty::Perfect,
+        ] {
+            let mov = get_pos(Opponent::Computer(difficulty), &board, &GameCell::Circle).unwrap();
+            assert!(board.available_moves().contains(&mov));
+        }
+        assert_eq!(
+            get_pos(Opponent::Computer(Difficulty::Perfect), &board, &GameCell::Circle),
+            Ok(Position { x: 2, y: 0 })
+        );
+    }
+
+    #[test]
+    fn test_order_and_chaos() {
+        let mut board = Board::new(BoardConfig::order_and_chaos(Role::Order));
+        for x in 1..5 {
+            board.set_cell_force(Position { x, y: 2 }, GameCell::Circle);
+        }
+        board.set_cell_force(Position { x: 0, y: 2 }, GameCell::Cross);
+        // Order finishes the row with a circle, and Chaos has to spoil it with a cross
+        let win = (Position { x: 5, y: 2 }, GameCell::Circle);
+        let order = WildTurn::new(board.clone(), GameCell::Cross);
+        assert_eq!(Mcts::with_seed(100, 1.4, 5).search(&order), Some(win));
+        let chaos = WildTurn::new(board.clone(), GameCell::Circle);
+        assert_eq!(
+            Mcts::with_seed(100, 1.4, 5).search(&chaos),
+            Some((win.0, GameCell::Cross))
+        );
+
+        let (pos, _) = get_wild_move(Opponent::Computer(Difficulty::Easy), &board, GameCell::Circle).unwrap();
+        assert!(board.available_moves().contains(&pos));
+    }
+
+    #[test]
+    fn test_gravity() {
+        let mut board = Board::new(BoardConfig::connect_four());
+        for x in [2, 3, 4] {
+            board.set_cell(Position { x, y: 0 }, GameCell::Cross).unwrap();
+        }
+        board.set_cell(Position { x: 5, y: 0 }, GameCell::Circle).unwrap();
+        board.set_cell(Position { x: 3, y: 0 }, GameCell::Circle).unwrap();
+
+        let mov = get_pos(Opponent::Random, &board, &GameCell::Circle).unwrap();
+        assert!(board.available_moves().contains(&mov));
+        // O blocks the open end of the bottom row, which X takes to win when it is their turn
+        let block = Position { x: 1, y: 5 };
+        assert_eq!(get_pos(Opponent::Minimax, &board, &GameCell::Circle), Ok(block));
+        assert_eq!(get_pos(Opponent::Minimax, &board, &GameCell::Cross), Ok(block));
+        let mov = get_pos(Opponent::Computer(Difficulty::Hard), &board, &GameCell::Cross).unwrap();
+        assert!(board.available_moves().contains(&mov));
+    }
+
+    #[test]
+    fn test_analyse() {
+        let board: Board = "XX./OO./...".parse().unwrap();
+        let analysis = analyse(&board, GameCell::Cross).unwrap();
+        let solved = Solver::new().solve_moves(&board, GameCell::Cross).unwrap();
+        assert_eq!(analysis.len(), solved.len());
+        for (analysis, (position, value)) in analysis.iter().zip(solved) {
+            assert_eq!(
+                (analysis.position, analysis.cell, analysis.value),
+                (position, GameCell::Cross, value)
+            );
+        }
+
+        let best = hint(&board, GameCell::Cross).unwrap();
+        assert_eq!(best.position, Position { x: 2, y: 0 });
+        assert_eq!(best.value.to_string(), "wins in 1 ply");
+        assert!(!best.is_blunder(&analysis));
+        // Anything but winning or blocking the middle row lets O win
+        let corner = analysis.iter().find(|m| m.position == Position { x: 0, y: 2 }).unwrap();
+        assert_eq!(
+            corner.value,
+            Value {
+                outcome: Outcome::Loss,
+                plies: 2
+            }
+        );
+        assert!(corner.is_blunder(&analysis));
+        assert!(corner.value < best.value);
+
+        let mut wild = Board::default();
+        wild.config.wild = true;
+        let analysis = analyse(&wild, GameCell::Circle).unwrap();
+        assert_eq!(analysis.len(), 18);
+        assert!(analysis.iter().any(|m| m.cell == GameCell::Cross));
+
+        let finished: Board = "XXX/OO./...".parse().unwrap();
+        assert_eq!(analyse(&finished, GameCell::Circle), Err(GameError::NoMovesAvailable));
+        assert_eq!(
+            analyse(&Board::new(BoardConfig::gomoku()), GameCell::Cross),
+            Err(GameError::BoardTooLarge)
+        );
+        assert_eq!(
+            hint(&Board::new(BoardConfig::connect_four()), GameCell::Cross),
+            Err(GameError::BoardTooLarge)
+        );
+        // A 6x6 board is small enough to solve once most of it has filled up
+        let mut board = Board::new(BoardConfig::new(6, 6, 4).unwrap());
+        assert_eq!(analyse(&board, GameCell::Cross), Err(GameError::BoardTooLarge));
+        for x in 0..5 {
+            for y in 0..5 {
+                let cell = if (x / 2 + y) % 2 == 0 {
+                    GameCell::Cross
+                } else {
+                    GameCell::Circle
+                };
+                board.set_cell_force(Position { x, y }, cell);
+            }
+        }
+        assert!(analyse(&board, GameCell::Cross).is_ok());
+    }
 }
@@ -0,0 +1,475 @@
+//! Qubic: tic-tac-toe on a 4x4x4 cube, seen as four stacked layers. Four in a row along any of
+//! the 76 lines of the cube wins: the rows, columns and diagonals of each layer, the pillars
+//! straight down through the layers, and the diagonals that cross from layer to layer.
+//!
+//! The cube holds 64 cells, so each side's pieces fit in a single `u64`.
+
+use std::sync::OnceLock;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::GameError,
+    game::{Cells, Player, State},
+    mcts::Mcts,
+    search::Searchable,
+    solver::Outcome,
+    update::{Difficulty, GameCell, Move, Opponent, Position, Position3},
+};
+
+/// Cells along each edge of the cube.
+pub const SIZE: usize = 4;
+
+/// Every line of the cube, as the mask of its four cells.
+pub fn lines() -> &'static [u64] {
+    static LINES: OnceLock<Vec<u64>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        // The 13 directions whose first non-zero step is forwards, so each line is found once
+        let mut directions = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if (dx, dy, dz) > (0, 0, 0) {
+                        directions.push((dx, dy, dz));
+                    }
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        for (dx, dy, dz) in directions {
+            for start in 0..SIZE * SIZE * SIZE {
+                let start = position(start);
+                let cells = (0..SIZE as isize)
+                    .map(|i| {
+                        let step = |from: usize, by: isize| {
+                            let to = from as isize + by * i;
+                            (0..SIZE as isize).contains(&to).then_some(to as usize)
+                        };
+                        Some(Position3 {
+                            x: step(start.x, dx)?,
+                            y: step(start.y, dy)?,
+                            z: step(start.z, dz)?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(cells) = cells {
+                    lines.push(cells.into_iter().fold(0, |mask, pos| mask | bit(pos)));
+                }
+            }
+        }
+        lines
+    })
+}
+
+fn index(pos: Position3) -> usize {
+    (pos.z * SIZE + pos.y) * SIZE + pos.x
+}
+
+fn position(index: usize) -> Position3 {
+    Position3 {
+        x: index % SIZE,
+        y: index / SIZE % SIZE,
+        z: index / (SIZE * SIZE),
+    }
+}
+
+fn bit(pos: Position3) -> u64 {
+    1 << index(pos)
+}
+
+/// A Qubic cube: one bit per cell for each side, layer by layer and row by row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QubicBoard {
+    pub crosses: u64,
+    pub circles: u64,
+}
+
+impl QubicBoard {
+    pub fn contains(pos: Position3) -> bool {
+        pos.x < SIZE && pos.y < SIZE && pos.z < SIZE
+    }
+
+    pub fn get_cell(&self, pos: Position3) -> Option<GameCell> {
+        if !QubicBoard::contains(pos) {
+            None
+        } else if self.crosses & bit(pos) != 0 {
+            Some(GameCell::Cross)
+        } else if self.circles & bit(pos) != 0 {
+            Some(GameCell::Circle)
+        } else {
+            Some(GameCell::Empty)
+        }
+    }
+
+    /// The cells of layer `z`, laid out like a flat [`Board`](crate::game::Board)'s.
+    pub fn layer(&self, z: usize) -> Cells {
+        (0..SIZE)
+            .map(|y| {
+                (0..SIZE)
+                    .map(|x| self.get_cell(Position3 { x, y, z }).unwrap_or(GameCell::Empty))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The number of pieces on the board.
+    pub fn moves(&self) -> usize {
+        (self.crosses | self.circles).count_ones() as usize
+    }
+
+    fn empty(&self) -> u64 {
+        !(self.crosses | self.circles)
+    }
+
+    /// Every empty cell, layer by layer, or none once the game is over.
+    pub fn available_moves(&self) -> Vec<Position3> {
+        if self.get_state() != State::Empty {
+            return Vec::new();
+        }
+        (0..SIZE * SIZE * SIZE)
+            .filter(|index| self.empty() >> index & 1 == 1)
+            .map(position)
+            .collect()
+    }
+
+    pub fn set_cell(&mut self, pos: Position3, cell: GameCell) -> Result<(), GameError> {
+        if pos.z >= SIZE {
+            return Err(GameError::NoSuchLayer(pos.z));
+        }
+        if !QubicBoard::contains(pos) {
+            return Err(GameError::OutOfBounds(pos.flat()));
+        }
+        if self.get_state() != State::Empty {
+            return Err(GameError::GameOver);
+        }
+        if self.get_cell(pos) != Some(GameCell::Empty) {
+            return Err(GameError::CellOccupied(pos.flat()));
+        }
+        self.set_cell_force(pos, cell);
+        Ok(())
+    }
+
+    /// Sets the cell at `pos`, which has to be on the board, without checking that the move is legal.
+    pub fn set_cell_force(&mut self, pos: Position3, cell: GameCell) {
+        let bit = bit(pos);
+        self.crosses &= !bit;
+        self.circles &= !bit;
+        match cell {
+            GameCell::Cross => self.crosses |= bit,
+            GameCell::Circle => self.circles |= bit,
+            GameCell::Empty => {}
+        }
+    }
+
+    pub fn get_state(&self) -> State {
+        for line in lines() {
+            if self.crosses & line == *line {
+                return State::Win(GameCell::Cross);
+            }
+            if self.circles & line == *line {
+                return State::Win(GameCell::Circle);
+            }
+        }
+        if self.empty() == 0 {
+            State::Draw
+        } else {
+            State::Empty
+        }
+    }
+}
+
+/// A Qubic board together with the piece that will be placed next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QubicTurn {
+    pub board: QubicBoard,
+    pub cell: GameCell,
+}
+
+impl Searchable for QubicTurn {
+    type Action = Position3;
+
+    fn actions(&self) -> Vec<Position3> {
+        self.board.available_moves()
+    }
+
+    fn play(&mut self, pos: Position3) {
+        self.board.set_cell_force(pos, self.cell);
+        self.cell = self.cell.opposite();
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.board.get_state() {
+            State::Win(_) => Some(Outcome::Loss),
+            State::Draw => Some(Outcome::Draw),
+            State::Empty => None,
+        }
+    }
+}
+
+/// The move the computer playing as `opponent` picks for `cell`. The cube is far too large to
+/// solve, so the stronger opponents search it with [`Mcts`].
+pub fn get_pos(opponent: Opponent, board: &QubicBoard, cell: GameCell) -> Result<Position3, GameError> {
+    let iterations = match opponent {
+        Opponent::Random => {
+            return board
+                .available_moves()
+                .choose(&mut rand::thread_rng())
+                .copied()
+                .ok_or(GameError::NoMovesAvailable)
+        }
+        Opponent::Minimax => Difficulty::Perfect.iterations(),
+        Opponent::Computer(difficulty) => difficulty.iterations(),
+        Opponent::Human | Opponent::Online | Opponent::Custom => return Err(GameError::OpponentCannotMove(opponent)),
+    };
+    let turn = QubicTurn { board: *board, cell };
+    Mcts::new(iterations, 1.4)
+        .search(&turn)
+        .ok_or(GameError::NoMovesAvailable)
+}
+
+/// A piece placed during a game of Qubic.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QubicPly {
+    pub player: Player,
+    pub position: Position3,
+    pub cell: GameCell,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum QubicState {
+    GameOver(Option<Player>, QubicBoard),
+    GameInProgress(QubicBoard, Player, Position3),
+}
+
+/// A game of Qubic, driven the same way as a [`Game`](crate::game::Game). The cursor moves
+/// within a layer with the arrow moves and between layers with [`Move::LayerUp`] and
+/// [`Move::LayerDown`]; [`Move::PlaceAt`] places in the cursor's layer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QubicGame {
+    pub board: QubicBoard,
+    pub current_position: Position3,
+    pub current_player: Player,
+    pub winner: Option<Player>,
+    pub opponent: Opponent,
+    should_continue: bool,
+    state_changed: bool,
+    #[serde(default)]
+    history: Vec<QubicPly>,
+}
+
+impl QubicGame {
+    /// A new game against `opponent`. Only the built-in opponents play Qubic, so
+    /// [`Opponent::Custom`] is refused with [`GameError::OpponentCannotMove`].
+    pub fn new(opponent: Opponent) -> Result<QubicGame, GameError> {
+        if opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(opponent));
+        }
+        Ok(QubicGame {
+            board: QubicBoard::default(),
+            current_position: Position3 { x: 0, y: 0, z: 0 },
+            current_player: Player::Player1,
+            winner: None,
+            opponent,
+            should_continue: true,
+            state_changed: true,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, mov: Move) -> Result<QubicState, GameError> {
+        let pos = self.current_position;
+        let in_layer = |at: Position| Position3 {
+            x: at.x,
+            y: at.y,
+            z: pos.z,
+        };
+        match mov {
+            Move::Up if pos.y > 0 => self.current_position.y -= 1,
+            Move::Down if pos.y + 1 < SIZE => self.current_position.y += 1,
+            Move::Left if pos.x > 0 => self.current_position.x -= 1,
+            Move::Right if pos.x + 1 < SIZE => self.current_position.x += 1,
+            Move::LayerUp if pos.z > 0 => self.current_position.z -= 1,
+            Move::LayerDown if pos.z + 1 < SIZE => self.current_position.z += 1,
+            Move::Up | Move::Down | Move::Left | Move::Right | Move::LayerUp | Move::LayerDown => {
+                return Err(GameError::CannotMove(mov))
+            }
+            Move::Place => self.play(pos)?,
+            Move::PlaceAt(at) => self.play(in_layer(at))?,
+            Move::PlaceAt3(at) => self.play(at)?,
+            Move::PlaceWith(cell) | Move::PlaceAtWith(_, cell) if cell != self.current_player.get_cell() => {
+                return Err(GameError::WrongPiece(cell))
+            }
+            Move::PlaceWith(_) => self.play(pos)?,
+            Move::PlaceAtWith(at, _) => self.play(in_layer(at))?,
+            Move::PlaceNumber(number) | Move::PlaceNumberAt(_, number) => return Err(GameError::WrongNumber(number)),
+        }
+        self.state_changed = true;
+        Ok(self.get_state().unwrap())
+    }
+
+    /// Places the current player's piece at `pos` and lets the computer reply if it is its turn.
+    fn play(&mut self, pos: Position3) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        // A deserialised game can still name a custom opponent, which could not reply
+        if self.opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(self.opponent));
+        }
+        self.board.set_cell(pos, self.current_player.get_cell())?;
+        self.history.push(QubicPly {
+            player: self.current_player,
+            position: pos,
+            cell: self.current_player.get_cell(),
+        });
+        match self.board.get_state() {
+            State::Empty => self.current_player = self.current_player.next(),
+            State::Win(_) => {
+                self.winner = Some(self.current_player);
+                self.should_continue = false;
+            }
+            State::Draw => self.should_continue = false,
+        }
+        self.state_changed = true;
+        let computer = !matches!(self.opponent, Opponent::Human | Opponent::Online);
+        if self.should_continue && computer && self.current_player == Player::Player2 {
+            let pos = get_pos(self.opponent, &self.board, self.current_player.get_cell())?;
+            return self.play(pos);
+        }
+        Ok(())
+    }
+
+    /// The pieces placed so far, in the order they were played.
+    pub fn history(&self) -> &[QubicPly] {
+        &self.history
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.should_continue
+    }
+
+    pub fn get_state(&mut self) -> Option<QubicState> {
+        if !self.state_changed {
+            return None;
+        }
+        self.state_changed = false;
+        if self.is_over() {
+            Some(QubicState::GameOver(self.winner, self.board))
+        } else {
+            Some(QubicState::GameInProgress(
+                self.board,
+                self.current_player,
+                self.current_position,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(lines().len(), 76);
+        assert!(lines().iter().all(|line| line.count_ones() == 4));
+        // The corners and the eight cells at the heart of the cube lie on seven lines, the rest on four
+        let through = |pos: Position3| lines().iter().filter(|line| *line & bit(pos) != 0).count();
+        assert_eq!(through(Position3 { x: 0, y: 0, z: 0 }), 7);
+        assert_eq!(through(Position3 { x: 3, y: 0, z: 3 }), 7);
+        assert_eq!(through(Position3 { x: 1, y: 2, z: 1 }), 7);
+        assert_eq!(through(Position3 { x: 1, y: 0, z: 0 }), 4);
+        assert_eq!(through(Position3 { x: 1, y: 1, z: 0 }), 4);
+    }
+
+    #[test]
+    fn test_win_across_layers() {
+        let mut board = QubicBoard::default();
+        for i in 0..3 {
+            board
+                .set_cell(Position3 { x: i, y: 3 - i, z: i }, GameCell::Circle)
+                .unwrap();
+        }
+        assert_eq!(board.get_state(), State::Empty);
+        assert_eq!(board.layer(1)[2][1], GameCell::Circle);
+        board
+            .set_cell(Position3 { x: 3, y: 0, z: 3 }, GameCell::Circle)
+            .unwrap();
+        assert_eq!(board.get_state(), State::Win(GameCell::Circle));
+        assert!(board.available_moves().is_empty());
+        assert_eq!(
+            board.set_cell(Position3 { x: 0, y: 0, z: 0 }, GameCell::Cross),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut game = QubicGame::new(Opponent::Human).unwrap();
+        assert_eq!(game.update(Move::LayerUp), Err(GameError::CannotMove(Move::LayerUp)));
+        for _ in 0..3 {
+            game.update(Move::LayerDown).unwrap();
+        }
+        assert_eq!(
+            game.update(Move::LayerDown),
+            Err(GameError::CannotMove(Move::LayerDown))
+        );
+        game.update(Move::Right).unwrap();
+        game.update(Move::Place).unwrap();
+        assert_eq!(
+            game.board.get_cell(Position3 { x: 1, y: 0, z: 3 }),
+            Some(GameCell::Cross)
+        );
+        game.update(Move::PlaceAt(Position { x: 2, y: 2 })).unwrap();
+        assert_eq!(
+            game.board.get_cell(Position3 { x: 2, y: 2, z: 3 }),
+            Some(GameCell::Circle)
+        );
+        assert_eq!(
+            game.update(Move::PlaceAt3(Position3 { x: 0, y: 0, z: 4 })),
+            Err(GameError::NoSuchLayer(4))
+        );
+        assert_eq!(
+            game.update(Move::PlaceAt3(Position3 { x: 1, y: 0, z: 3 })),
+            Err(GameError::CellOccupied(Position { x: 1, y: 0 }))
+        );
+        assert_eq!(game.history().len(), 2);
+    }
+
+    #[test]
+    fn test_game_against_computer() {
+        let mut game = QubicGame::new(Opponent::Computer(Difficulty::Easy)).unwrap();
+        while !game.is_over() {
+            let pos = *game.board.available_moves().choose(&mut rand::thread_rng()).unwrap();
+            game.update(Move::PlaceAt3(pos)).unwrap();
+            assert!(game.is_over() || game.current_player == Player::Player1);
+        }
+        match game.board.get_state() {
+            State::Win(_) => assert_eq!(game.winner, game.history().last().map(|ply| ply.player)),
+            state => assert_eq!(state, State::Draw),
+        }
+        assert_eq!(
+            QubicGame::new(Opponent::Custom),
+            Err(GameError::OpponentCannotMove(Opponent::Custom))
+        );
+    }
+
+    #[test]
+    fn test_mcts_blocks_a_pillar() {
+        let mut board = QubicBoard::default();
+        for z in 0..3 {
+            board.set_cell_force(Position3 { x: 2, y: 1, z }, GameCell::Cross);
+        }
+        board.set_cell_force(Position3 { x: 0, y: 0, z: 0 }, GameCell::Circle);
+        board.set_cell_force(Position3 { x: 3, y: 3, z: 3 }, GameCell::Circle);
+        let turn = QubicTurn {
+            board,
+            cell: GameCell::Circle,
+        };
+        assert_eq!(
+            Mcts::with_seed(200, 1.4, 3).search(&turn),
+            Some(Position3 { x: 2, y: 1, z: 3 })
+        );
+    }
+}
@@ -0,0 +1,659 @@
+//! A portable text format for complete games, modelled on chess PGN.
+//!
+//! A record starts with tags of the form `[Name "value"]`, one per line, followed by the moves.
+//! Cells are written with a column letter and a row number, `a1` being the top-left corner. After
+//! `z` columns go on as in a spreadsheet, with `aa`, `ab` and so on.
+//! In wild games and Order and Chaos the piece placed comes first, as in `Ob2`.
+//! A move may be followed by `@` and the milliseconds since the start of the game, and by a
+//! comment in braces, in which `}` and `\` are escaped with a backslash.
+//! The record ends with the result: `1-0`, `0-1`, `1/2-1/2`, or `*` for a game still in progress.
+//!
+//! ```text
+//! [Player1 "Alice"]
+//! [Player2 "Bob"]
+//! [Opponent "Human"]
+//! [Start "1700000000"]
+//! [Board "3x3"]
+//! [WinLength "3"]
+//! [Result "1-0"]
+//!
+//! 1. b2 @1200 {the centre} a1 @3400 2. c1 b1 3. a3 1-0
+//! ```
+//!
+//! Games with other rules name them in a `Rules` tag, such as `[Rules "Misere Gravity"]`.
+//! Order and Chaos is named by the role Player 1 plays, `Order` or `Chaos`; Gomoku's rules are
+//! `Exact` when an overline does not win and `Renju` when the first player's moves are restricted.
+//! Unknown tags are ignored, and a missing board defaults to the classic 3x3 game.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::RecordError,
+    game::{BoardConfig, Game, Player, Role},
+    update::{Difficulty, GameCell, Move, Opponent, Position},
+};
+
+/// How a recorded game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameResult {
+    Win(Player),
+    Draw,
+    /// The game was saved before it finished
+    Unfinished,
+}
+
+impl GameResult {
+    pub fn of(game: &Game) -> GameResult {
+        match game.winner {
+            Some(player) => GameResult::Win(player),
+            None if game.is_over() => GameResult::Draw,
+            None => GameResult::Unfinished,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameResult::Win(Player::Player1) => write!(f, "1-0"),
+            GameResult::Win(Player::Player2) => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+            GameResult::Unfinished => write!(f, "*"),
+        }
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<GameResult, ()> {
+        match s {
+            "1-0" => Ok(GameResult::Win(Player::Player1)),
+            "0-1" => Ok(GameResult::Win(Player::Player2)),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unfinished),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One move of a record.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMove {
+    pub position: Position,
+    /// The piece placed, written before the cell as in `Xb2`. Only recorded when either piece may be placed
+    #[serde(default)]
+    pub cell: Option<GameCell>,
+    /// Milliseconds since the start of the game
+    pub time: Option<u64>,
+    /// Written in braces, with `}` and `\` escaped
+    pub comment: Option<String>,
+}
+
+impl RecordedMove {
+    pub fn new(position: Position) -> RecordedMove {
+        RecordedMove {
+            position,
+            cell: None,
+            time: None,
+            comment: None,
+        }
+    }
+}
+
+/// A complete game: who played, how it was set up, every move and the result.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub player1: String,
+    pub player2: String,
+    pub opponent: Opponent,
+    /// Seconds since the Unix epoch
+    pub start: Option<u64>,
+    pub config: BoardConfig,
+    pub result: GameResult,
+    pub moves: Vec<RecordedMove>,
+}
+
+impl Record {
+    /// The record of `game` so far. Names, start time, move times and comments are left for the
+    /// caller to fill in.
+    pub fn from_game(game: &Game) -> Record {
+        Record {
+            player1: "Player 1".to_string(),
+            player2: "Player 2".to_string(),
+            opponent: game.opponent,
+            start: None,
+            config: game.board.config,
+            result: GameResult::of(game),
+            moves: game
+                .history()
+                .iter()
+                .map(|ply| RecordedMove {
+                    cell: game.board.config.free_pieces().then_some(ply.cell),
+                    ..RecordedMove::new(ply.position)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Game {
+    /// Replays `record` move by move, checking that every move is legal and that the game ends
+    /// with the recorded result. The computer does not reply during the replay, but it will play
+    /// on from the final position if the game is unfinished. A [`Opponent::Custom`] strategy
+    /// cannot be stored in a record and has to be played by hand.
+    pub fn from_record(record: &Record) -> Result<Game, RecordError> {
+        let mut game = Game::with_config(Opponent::Human, record.config).map_err(|_| RecordError::InvalidValue {
+            tag: "WinLength".to_string(),
+            value: record.config.win_length.to_string(),
+        })?;
+        for (ply, mov) in record.moves.iter().enumerate() {
+            let mov = match mov.cell {
+                Some(cell) => Move::PlaceAtWith(mov.position, cell),
+                None => Move::PlaceAt(mov.position),
+            };
+            game.update(mov)
+                .map_err(|error| RecordError::IllegalMove { ply: ply + 1, error })?;
+        }
+        let actual = GameResult::of(&game);
+        if actual != record.result {
+            return Err(RecordError::ResultMismatch {
+                recorded: record.result,
+                actual,
+            });
+        }
+        game.opponent = record.opponent;
+        Ok(game)
+    }
+}
+
+fn opponent_name(opponent: Opponent) -> &'static str {
+    match opponent {
+        Opponent::Online => "Online",
+        Opponent::Human => "Human",
+        Opponent::Random => "Random",
+        Opponent::Minimax => "Minimax",
+        Opponent::Computer(Difficulty::Easy) => "Computer(Easy)",
+        Opponent::Computer(Difficulty::Medium) => "Computer(Medium)",
+        Opponent::Computer(Difficulty::Hard) => "Computer(Hard)",
+        Opponent::Computer(Difficulty::Perfect) => "Computer(Perfect)",
+        Opponent::Custom => "Custom",
+    }
+}
+
+fn parse_opponent(name: &str) -> Option<Opponent> {
+    match name {
+        "Online" => Some(Opponent::Online),
+        "Human" => Some(Opponent::Human),
+        "Random" => Some(Opponent::Random),
+        "Minimax" => Some(Opponent::Minimax),
+        "Computer(Easy)" => Some(Opponent::Computer(Difficulty::Easy)),
+        "Computer(Medium)" => Some(Opponent::Computer(Difficulty::Medium)),
+        "Computer(Hard)" => Some(Opponent::Computer(Difficulty::Hard)),
+        "Computer(Perfect)" => Some(Opponent::Computer(Difficulty::Perfect)),
+        "Custom" => Some(Opponent::Custom),
+        _ => None,
+    }
+}
+
+/// The cell's name, e.g. `b2` for the centre of a 3x3 board. Columns run from `a` to `z`, then
+/// from `aa` to `az`, `ba` to `bz` and so on.
+pub fn cell_name(pos: Position) -> String {
+    let mut column = Vec::new();
+    let mut x = pos.x + 1;
+    while x > 0 {
+        column.push((b'a' + ((x - 1) % 26) as u8) as char);
+        x = (x - 1) / 26;
+    }
+    column
+        .into_iter()
+        .rev()
+        .chain(format!("{}", pos.y + 1).chars())
+        .collect()
+}
+
+/// The position named by `name`, as written by [`cell_name`].
+pub fn parse_cell(name: &str) -> Option<Position> {
+    let digits = name.find(|c: char| !c.is_ascii_lowercase())?;
+    let (column, row) = name.split_at(digits);
+    if column.is_empty() || !row.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let x = column
+        .bytes()
+        .try_fold(0_usize, |x, c| x.checked_mul(26)?.checked_add((c - b'a') as usize + 1))?;
+    let row = row.parse::<usize>().ok().filter(|row| *row > 0)?;
+    Some(Position { x: x - 1, y: row - 1 })
+}
+
+fn write_tag(f: &mut fmt::Formatter, name: &str, value: &str) -> fmt::Result {
+    writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_tag(f, "Player1", &self.player1)?;
+        write_tag(f, "Player2", &self.player2)?;
+        write_tag(f, "Opponent", opponent_name(self.opponent))?;
+        if let Some(start) = self.start {
+            write_tag(f, "Start", &start.to_string())?;
+        }
+        write_tag(f, "Board", &format!("{}x{}", self.config.width, self.config.height))?;
+        write_tag(f, "WinLength", &self.config.win_length.to_string())?;
+        let rules = [
+            (self.config.misere, "Misere"),
+            (self.config.gravity, "Gravity"),
+            (self.config.wild, "Wild"),
+            (self.config.exact_length, "Exact"),
+            (self.config.renju, "Renju"),
+            (self.config.order_and_chaos == Some(Role::Order), "Order"),
+            (self.config.order_and_chaos == Some(Role::Chaos), "Chaos"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect::<Vec<_>>();
+        if !rules.is_empty() {
+            write_tag(f, "Rules", &rules.join(" "))?;
+        }
+        write_tag(f, "Result", &self.result.to_string())?;
+        writeln!(f)?;
+
+        for (ply, mov) in self.moves.iter().enumerate() {
+            if ply % 2 == 0 {
+                write!(f, "{}. ", ply / 2 + 1)?;
+            }
+            match mov.cell {
+                Some(GameCell::Cross) => write!(f, "X")?,
+                Some(GameCell::Circle) => write!(f, "O")?,
+                _ => {}
+            }
+            write!(f, "{} ", cell_name(mov.position))?;
+            if let Some(time) = mov.time {
+                write!(f, "@{} ", time)?;
+            }
+            if let Some(comment) = &mov.comment {
+                write!(f, "{{{}}} ", comment.replace('\\', "\\\\").replace('}', "\\}"))?;
+            }
+        }
+        writeln!(f, "{}", self.result)
+    }
+}
+
+/// Splits `[Name "value"]` into its name and unescaped value.
+fn parse_tag(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some((name, unescaped))
+}
+
+fn parse_config(board: &str, win_length: Option<&str>) -> Option<BoardConfig> {
+    let (width, height) = board.split_once('x')?;
+    let width = width.parse::<usize>().ok().filter(|width| *width > 0)?;
+    let height = height.parse::<usize>().ok().filter(|height| *height > 0)?;
+    let win_length = match win_length {
+        Some(length) => length.parse::<usize>().ok()?,
+        None => BoardConfig::default().win_length.min(width).min(height),
+    };
+    BoardConfig::new(width, height, win_length).ok()
+}
+
+impl FromStr for Record {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Record, RecordError> {
+        let mut record = Record {
+            player1: "Player 1".to_string(),
+            player2: "Player 2".to_string(),
+            opponent: Opponent::Human,
+            start: None,
+            config: BoardConfig::default(),
+            result: GameResult::Unfinished,
+            moves: Vec::new(),
+        };
+        let invalid = |tag: &str, value: &str| RecordError::InvalidValue {
+            tag: tag.to_string(),
+            value: value.to_string(),
+        };
+
+        let mut board = None;
+        let mut win_length = None;
+        let mut rules = None;
+        let mut tagged_result = None;
+        let mut rest = s.trim_start();
+        while rest.starts_with('[') {
+            let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+            let line = line.trim();
+            let (name, value) = parse_tag(line).ok_or_else(|| RecordError::InvalidTag(line.to_string()))?;
+            match name {
+                "Player1" => record.player1 = value,
+                "Player2" => record.player2 = value,
+                "Opponent" => record.opponent = parse_opponent(&value).ok_or_else(|| invalid(name, &value))?,
+                "Start" => record.start = Some(value.parse().map_err(|_| invalid(name, &value))?),
+                "Board" => board = Some(value),
+                "WinLength" => win_length = Some(value),
+                "Rules" => rules = Some(value),
+                "Result" => tagged_result = Some(value.parse().map_err(|_| invalid(name, &value))?),
+                _ => {}
+            }
+            rest = next.trim_start();
+        }
+        if board.is_some() || win_length.is_some() {
+            let board = board.unwrap_or_else(|| "3x3".to_string());
+            record.config = parse_config(&board, win_length.as_deref()).ok_or_else(|| invalid("Board", &board))?;
+        }
+        for rule in rules.iter().flat_map(|rules| rules.split_whitespace()) {
+            match rule {
+                "Standard" => {}
+                "Misere" => record.config.misere = true,
+                "Gravity" => record.config.gravity = true,
+                "Wild" => record.config.wild = true,
+                "Exact" => record.config.exact_length = true,
+                "Renju" => record.config.renju = true,
+                "Order" => record.config.order_and_chaos = Some(Role::Order),
+                "Chaos" => record.config.order_and_chaos = Some(Role::Chaos),
+                _ => return Err(invalid("Rules", rules.as_deref().unwrap_or_default())),
+            }
+        }
+
+        let mut moves_result = None;
+        let mut chars = rest.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            if c == '{' {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => comment.push(c),
+                            None => return Err(RecordError::UnterminatedComment),
+                        },
+                        Some((_, c)) => comment.push(c),
+                        None => return Err(RecordError::UnterminatedComment),
+                    }
+                }
+                // A comment before the first move has nothing to annotate and is dropped
+                if let Some(last) = record.moves.last_mut() {
+                    last.comment = Some(comment.trim().to_string());
+                }
+                continue;
+            }
+            let mut end = rest.len();
+            while let Some((index, c)) = chars.peek() {
+                if c.is_whitespace() || *c == '{' {
+                    end = *index;
+                    break;
+                }
+                chars.next();
+            }
+            let token = &rest[start..end];
+
+            if moves_result.is_some() {
+                return Err(RecordError::InvalidToken(token.to_string()));
+            }
+            if let Ok(result) = token.parse::<GameResult>() {
+                moves_result = Some(result);
+            } else if let Some(time) = token.strip_prefix('@') {
+                let last = record.moves.last_mut();
+                match (last, time.parse::<u64>()) {
+                    (Some(last), Ok(time)) => last.time = Some(time),
+                    _ => return Err(RecordError::InvalidToken(token.to_string())),
+                }
+            } else if token
+                .strip_suffix('.')
+                .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+            {
+                // Move numbers are only there for people reading the record
+            } else {
+                let (cell, name) = match token.split_at_checked(1) {
+                    Some(("X", name)) => (Some(GameCell::Cross), name),
+                    Some(("O", name)) => (Some(GameCell::Circle), name),
+                    _ => (None, token),
+                };
+                let position = parse_cell(name).ok_or_else(|| RecordError::InvalidToken(token.to_string()))?;
+                record.moves.push(RecordedMove {
+                    cell,
+                    ..RecordedMove::new(position)
+                });
+            }
+        }
+
+        record.result = match (tagged_result, moves_result) {
+            (Some(tagged), Some(written)) if tagged != written => {
+                return Err(RecordError::ResultMismatch {
+                    recorded: tagged,
+                    actual: written,
+                })
+            }
+            (Some(result), _) | (None, Some(result)) => result,
+            (None, None) => GameResult::Unfinished,
+        };
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::GameError, game::State};
+
+    fn play(game: &mut Game, cells: &[&str]) {
+        for cell in cells {
+            game.update(Move::PlaceAt(parse_cell(cell).unwrap())).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cell_names() {
+        assert_eq!(cell_name(Position { x: 0, y: 0 }), "a1");
+        assert_eq!(cell_name(Position { x: 1, y: 1 }), "b2");
+        assert_eq!(cell_name(Position { x: 14, y: 14 }), "o15");
+        assert_eq!(parse_cell("o15"), Some(Position { x: 14, y: 14 }));
+        // Past z the columns go on with two letters
+        assert_eq!(cell_name(Position { x: 25, y: 0 }), "z1");
+        assert_eq!(cell_name(Position { x: 26, y: 0 }), "aa1");
+        assert_eq!(cell_name(Position { x: 27 * 26, y: 2 }), "aaa3");
+        for x in [0, 25, 26, 51, 52, 300, 701, 702, 5000] {
+            let pos = Position { x, y: 7 };
+            assert_eq!(parse_cell(&cell_name(pos)), Some(pos));
+        }
+        for name in ["", "a", "1a", "a0", "A1", "a1b", "a-1"] {
+            assert_eq!(parse_cell(name), None);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = Game::new(Opponent::Human);
+        play(&mut game, &["b2", "a1", "c1", "b1", "a3"]);
+        assert_eq!(game.winner, Some(Player::Player1));
+
+        let mut record = Record::from_game(&game);
+        record.player1 = "Alice \"the bold\"".to_string();
+        record.start = Some(1_700_000_000);
+        record.moves[0].time = Some(1200);
+        record.moves[0].comment = Some("the centre".to_string());
+        record.moves[1].comment = Some("a {corner} \\ at last".to_string());
+        record.moves[3].time = Some(5400);
+
+        let text = record.to_string();
+        assert!(text.contains("1. b2 @1200 {the centre} a1 {a {corner\\} \\\\ at last} 2. c1 b1 @5400 3. a3 1-0"));
+        let parsed = text.parse::<Record>().unwrap();
+        assert_eq!(parsed, record);
+
+        let replayed = Game::from_record(&parsed).unwrap();
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.history(), game.history());
+        assert_eq!(replayed.winner, Some(Player::Player1));
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut game = Game::with_config(Opponent::Human, BoardConfig::connect_four()).unwrap();
+        play(&mut game, &["d1", "d1", "c1"]);
+        let mut record = Record::from_game(&game);
+        record.config.misere = true;
+        let text = record.to_string();
+        assert!(text.contains("[Rules \"Misere Gravity\"]"));
+        assert!(text.contains("1. d6 d5 2. c6 *"));
+        assert_eq!(text.parse::<Record>().unwrap().config, record.config);
+
+        let game = Game::with_config(Opponent::Human, BoardConfig::order_and_chaos(Role::Chaos)).unwrap();
+        let text = Record::from_game(&game).to_string();
+        assert!(text.contains("[Board \"6x6\"]"));
+        assert!(text.contains("[Rules \"Chaos\"]"));
+        assert_eq!(text.parse::<Record>().unwrap().config, game.board.config);
+
+        let config = BoardConfig {
+            exact_length: true,
+            renju: true,
+            ..BoardConfig::gomoku()
+        };
+        let text = Record::from_game(&Game::with_config(Opponent::Human, config).unwrap()).to_string();
+        assert!(text.contains("[Rules \"Exact Renju\"]"));
+        assert_eq!(text.parse::<Record>().unwrap().config, config);
+
+        assert_eq!(
+            "[Rules \"Gravity Sideways\"]".parse::<Record>().unwrap_err(),
+            RecordError::InvalidValue {
+                tag: "Rules".to_string(),
+                value: "Gravity Sideways".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_wild_moves() {
+        let config = BoardConfig {
+            wild: true,
+            ..BoardConfig::default()
+        };
+        let mut game = Game::with_config(Opponent::Human, config).unwrap();
+        for (pos, cell) in [((1, 1), GameCell::Circle), ((0, 0), GameCell::Cross)] {
+            game.update(Move::PlaceAtWith(Position { x: pos.0, y: pos.1 }, cell))
+                .unwrap();
+        }
+        let record = Record::from_game(&game);
+        let text = record.to_string();
+        assert!(text.contains("[Rules \"Wild\"]"));
+        assert!(text.contains("1. Ob2 Xa1 *"));
+
+        let parsed: Record = text.parse().unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(Game::from_record(&parsed).unwrap().board, game.board);
+    }
+
+    #[test]
+    fn test_unfinished_game_against_computer() {
+        let mut game =
+            Game::with_config(Opponent::Computer(Difficulty::Hard), BoardConfig::new(4, 4, 3).unwrap()).unwrap();
+        play(&mut game, &["b2"]);
+        let record = Record::from_game(&game);
+        assert_eq!(record.result, GameResult::Unfinished);
+        assert_eq!(record.moves.len(), 2);
+
+        let mut replayed = Game::from_record(&record.to_string().parse().unwrap()).unwrap();
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.opponent, Opponent::Computer(Difficulty::Hard));
+        assert_eq!(replayed.current_player, Player::Player1);
+        let free = replayed.board.available_moves()[0];
+        replayed.update(Move::PlaceAt(free)).unwrap();
+        assert_eq!(replayed.history().len(), 4);
+        assert_eq!(replayed.board.get_state(), State::Empty);
+    }
+
+    #[test]
+    fn test_wide_board() {
+        let mut game = Game::with_config(Opponent::Human, BoardConfig::new(30, 2, 3).unwrap()).unwrap();
+        play(&mut game, &["ab1", "ab2", "ac1", "ac2", "ad1"]);
+        assert_eq!(game.winner, Some(Player::Player1));
+        let text = Record::from_game(&game).to_string();
+        assert!(text.contains("[Board \"30x2\"]"));
+        assert!(text.contains("1. ab1 ab2 2. ac1 ac2 3. ad1 1-0"));
+        assert_eq!(Game::from_record(&text.parse().unwrap()).unwrap().board, game.board);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "[Result 1-0]".parse::<Record>().unwrap_err(),
+            RecordError::InvalidTag("[Result 1-0]".to_string())
+        );
+        assert_eq!(
+            "[Opponent \"Grandmaster\"]".parse::<Record>().unwrap_err(),
+            RecordError::InvalidValue {
+                tag: "Opponent".to_string(),
+                value: "Grandmaster".to_string()
+            }
+        );
+        assert_eq!(
+            "1. b2 {unclosed".parse::<Record>().unwrap_err(),
+            RecordError::UnterminatedComment
+        );
+        assert_eq!(
+            "1. b2 zz *".parse::<Record>().unwrap_err(),
+            RecordError::InvalidToken("zz".to_string())
+        );
+        assert_eq!(
+            "1. b2 * a1".parse::<Record>().unwrap_err(),
+            RecordError::InvalidToken("a1".to_string())
+        );
+        assert!(matches!(
+            "[Result \"1-0\"]\n\n1. b2 0-1".parse::<Record>(),
+            Err(RecordError::ResultMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_replay_checks_legality() {
+        let record = "1. b2 b2 *".parse::<Record>().unwrap();
+        assert_eq!(
+            Game::from_record(&record).unwrap_err(),
+            RecordError::IllegalMove {
+                ply: 2,
+                error: GameError::CellOccupied(Position { x: 1, y: 1 })
+            }
+        );
+
+        let record = "1. a1 b1 2. a2 b2 3. a3 b3 1-0".parse::<Record>().unwrap();
+        assert_eq!(
+            Game::from_record(&record).unwrap_err(),
+            RecordError::IllegalMove {
+                ply: 6,
+                error: GameError::GameOver
+            }
+        );
+
+        let record = "[Board \"3x3\"]\n1. d1 *".parse::<Record>().unwrap();
+        assert!(matches!(
+            Game::from_record(&record),
+            Err(RecordError::IllegalMove { ply: 1, .. })
+        ));
+
+        let record = "1. a1 b1 2. a2 b2 3. a3 1/2-1/2".parse::<Record>().unwrap();
+        assert_eq!(
+            Game::from_record(&record).unwrap_err(),
+            RecordError::ResultMismatch {
+                recorded: GameResult::Draw,
+                actual: GameResult::Win(Player::Player1)
+            }
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use crate::{
+    game::{Board, State},
+    solver::Outcome,
+    update::{GameCell, Position},
+};
+
+/// A two-player, alternating-turn position that the generic searches in this crate can explore.
+pub trait Searchable: Clone {
+    type Action: Copy + PartialEq + fmt::Debug;
+
+    /// Every move available to the side to move. Empty once the game is over.
+    fn actions(&self) -> Vec<Self::Action>;
+
+    /// Plays `action` for the side to move and hands the turn to the other side.
+    fn play(&mut self, action: Self::Action);
+
+    /// The result for the side to move, or `None` while the game is still going.
+    fn outcome(&self) -> Option<Outcome>;
+}
+
+/// A board together with the piece that will be placed next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    pub board: Board,
+    pub cell: GameCell,
+}
+
+impl Turn {
+    pub fn new(board: Board, cell: GameCell) -> Turn {
+        Turn { board, cell }
+    }
+}
+
+impl Searchable for Turn {
+    type Action = Position;
+
+    fn actions(&self) -> Vec<Position> {
+        match self.board.get_state() {
+            State::Empty => self.board.available_moves(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn play(&mut self, pos: Position) {
+        self.board.set_cell_force(pos, self.cell);
+        self.cell = self.cell.opposite();
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        outcome(&self.board, self.cell)
+    }
+}
+
+/// A board where the side to move picks the piece as well as the cell, as in wild games and
+/// Order and Chaos. `cell` is the side to move's own piece, which tells the sides apart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WildTurn {
+    pub board: Board,
+    pub cell: GameCell,
+}
+
+impl WildTurn {
+    pub fn new(board: Board, cell: GameCell) -> WildTurn {
+        WildTurn { board, cell }
+    }
+}
+
+impl Searchable for WildTurn {
+    type Action = (Position, GameCell);
+
+    fn actions(&self) -> Vec<(Position, GameCell)> {
+        match self.board.get_state() {
+            State::Empty => self
+                .board
+                .available_moves()
+                .into_iter()
+                .flat_map(|pos| [(pos, GameCell::Cross), (pos, GameCell::Circle)])
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn play(&mut self, (pos, piece): (Position, GameCell)) {
+        self.board.set_cell_force(pos, piece);
+        self.cell = self.cell.opposite();
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        outcome(&self.board, self.cell)
+    }
+}
+
+/// The result of `board` for the side to move, whose own piece is `cell`.
+fn outcome(board: &Board, cell: GameCell) -> Option<Outcome> {
+    match board.get_state() {
+        // A wild line belongs to whoever just moved, whichever piece it is made of
+        State::Win(_) if board.config.wild && board.config.misere => Some(Outcome::Win),
+        State::Win(_) if board.config.wild => Some(Outcome::Loss),
+        State::Win(winner) if winner == cell => Some(Outcome::Win),
+        State::Win(_) => Some(Outcome::Loss),
+        State::Draw => Some(Outcome::Draw),
+        State::Empty => None,
+    }
+}
@@ -0,0 +1,709 @@
+use std::{cmp::Ordering, collections::HashMap, fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitboard::{self, BitBoard, Layout, Mask},
+    error::GameError,
+    game::{Board, BoardConfig, State, Transform},
+    update::{GameCell, Position},
+};
+
+/// Score of a won position before subtracting the number of plies it takes to get there.
+pub(crate) const WIN: i32 = 1_000_000;
+
+/// Either piece may be played in a wild game.
+const WILD_PIECES: [GameCell; 2] = [GameCell::Cross, GameCell::Circle];
+
+/// Scores beyond this are forced results; anything closer to zero is a draw or a heuristic estimate.
+pub(crate) const DECISIVE: i32 = WIN - 100_000;
+
+/// Result of a position under perfect play, from the point of view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// The same result for the other side.
+    pub fn opposite(&self) -> Outcome {
+        match self {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss => Outcome::Win,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Outcome::Loss => 0,
+            Outcome::Draw => 1,
+            Outcome::Win => 2,
+        }
+    }
+}
+
+/// Outcomes are ordered from worst to best: a loss, a draw, then a win.
+impl Ord for Outcome {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Outcome {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The game-theoretic value of a position: its outcome and how many plies it takes to reach it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Value {
+    pub outcome: Outcome,
+    pub plies: usize,
+}
+
+impl Value {
+    /// The value of the position one ply earlier, for the player who made the move that led here.
+    pub fn before_move(&self) -> Value {
+        Value {
+            outcome: self.outcome.opposite(),
+            plies: self.plies + 1,
+        }
+    }
+
+    fn from_score(score: i32, board: &Board) -> Value {
+        if score > DECISIVE {
+            Value {
+                outcome: Outcome::Win,
+                plies: (WIN - score) as usize,
+            }
+        } else if score < -DECISIVE {
+            Value {
+                outcome: Outcome::Loss,
+                plies: (WIN + score) as usize,
+            }
+        } else {
+            // A draw is only reached once the board is full. Positions left undecided at the
+            // depth limit are reported the same way.
+            Value {
+                outcome: Outcome::Draw,
+                plies: board.config.width * board.config.height - board.moves(),
+            }
+        }
+    }
+}
+
+/// Values are ordered from worst to best for the side to move: by outcome, then a faster win is
+/// better than a slower one and a slower loss better than a faster one.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let plies = match self.outcome {
+            Outcome::Loss => self.plies.cmp(&other.plies),
+            Outcome::Draw | Outcome::Win => other.plies.cmp(&self.plies),
+        };
+        self.outcome.cmp(&other.outcome).then(plies)
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plies = if self.plies == 1 { "ply" } else { "plies" };
+        match self.outcome {
+            Outcome::Win => write!(f, "wins in {} {}", self.plies, plies),
+            Outcome::Draw => write!(f, "draws"),
+            Outcome::Loss => write!(f, "loses in {} {}", self.plies, plies),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// One step of a traced search, as recorded by [`Solver::trace`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Event {
+    /// The search went down to a position: the root, or the one after placing the piece at the position
+    Enter(Option<(Position, GameCell)>),
+    /// A cutoff in the position being searched skipped these moves
+    Cutoff(Vec<(Position, GameCell)>),
+    /// The search left the position with `score`. An undecided score is only an estimate, because
+    /// the depth limit stopped the search below it.
+    Leave { score: i32, bound: Bound, decided: bool },
+}
+
+/// How a traced search differs from a normal one, and what it has seen so far.
+struct Trace {
+    alpha_beta: bool,
+    symmetry: bool,
+    events: Vec<Event>,
+}
+
+/// Canonical crosses and circles of a position, and whether crosses are to move.
+type Key = (Mask, Mask, bool);
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    score: i32,
+    bound: Bound,
+    /// How many plies below this position were searched
+    depth: usize,
+}
+
+/// Negamax search with alpha-beta pruning, to the end of the game unless given a depth limit.
+///
+/// Positions are cached in a transposition table keyed by the board's canonical form, so
+/// rotations and reflections of a position are only ever searched once. The table is kept
+/// between calls, which makes it cheap to solve every position of a game with the same solver.
+pub struct Solver {
+    table: HashMap<Key, Entry>,
+    symmetries: Vec<Vec<usize>>,
+    layout: Option<Arc<Layout>>,
+    max_depth: Option<usize>,
+    trace: Option<Trace>,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            table: HashMap::new(),
+            symmetries: Vec::new(),
+            layout: None,
+            max_depth: None,
+            trace: None,
+        }
+    }
+
+    /// A solver that looks at most `depth` plies ahead and estimates the positions it reaches there.
+    /// `None` searches to the end of the game.
+    pub fn with_depth(depth: Option<usize>) -> Solver {
+        Solver {
+            max_depth: depth,
+            ..Solver::new()
+        }
+    }
+
+    /// Changes how far ahead later searches look. Entries already in the table stay usable.
+    pub fn set_depth(&mut self, depth: Option<usize>) {
+        self.max_depth = depth;
+    }
+
+    /// The value of `board` for `cell`, the side to move, or [`GameError::BoardTooLarge`] if the
+    /// board has more than [`MAX_CELLS`](crate::bitboard::MAX_CELLS) cells.
+    pub fn solve(&mut self, board: &Board, cell: GameCell) -> Result<Value, GameError> {
+        let (bits, _) = self.prepare(board)?;
+        let score = self.negamax(bits, cell, 0, -WIN, WIN);
+        Ok(Value::from_score(score, board))
+    }
+
+    /// The value of playing each available move for `cell`, in the order of [`Board::available_moves`].
+    pub fn solve_moves(&mut self, board: &Board, cell: GameCell) -> Result<Vec<(Position, Value)>, GameError> {
+        Ok(self
+            .score_moves(board, cell)?
+            .into_iter()
+            .map(|(pos, score)| (pos, Value::from_score(score, board)))
+            .collect())
+    }
+
+    /// The move for `cell` with the best value: the fastest win, otherwise a draw, otherwise the slowest loss.
+    pub fn best_move(&mut self, board: &Board, cell: GameCell) -> Result<Position, GameError> {
+        if board.get_state() != State::Empty {
+            return Err(GameError::NoMovesAvailable);
+        }
+        self.score_moves(board, cell)?
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, score)| *score)
+            .map(|(pos, _)| pos)
+            .ok_or(GameError::NoMovesAvailable)
+    }
+
+    /// The best move in a wild game on `board`: where to play and which piece to place. Wild
+    /// positions are worth the same to both players, so there is no side to move.
+    pub fn best_wild_move(&mut self, board: &Board) -> Result<(Position, GameCell), GameError> {
+        if board.get_state() != State::Empty {
+            return Err(GameError::NoMovesAvailable);
+        }
+        let (bits, layout) = self.prepare(board)?;
+        let mut best: Option<((Position, GameCell), i32)> = None;
+        for index in bitboard::bits(bits.moves(&layout)) {
+            for piece in WILD_PIECES {
+                let mut child = bits;
+                child.set(index, piece);
+                let score = -self.negamax(child, piece.opposite(), 1, -WIN, WIN);
+                if best.is_none_or(|(_, best)| score > best) {
+                    best = Some(((layout.position(index), piece), score));
+                }
+            }
+        }
+        best.map(|(mov, _)| mov).ok_or(GameError::NoMovesAvailable)
+    }
+
+    /// Searches `board` for `cell` as [`Solver::solve`] does, but without the transposition table
+    /// so that every position searched is recorded, and returns what the search did step by step.
+    /// Without `alpha_beta` nothing is pruned; with `symmetry` only one of the moves leading to
+    /// equivalent positions is searched.
+    pub(crate) fn trace(
+        &mut self,
+        board: &Board,
+        cell: GameCell,
+        alpha_beta: bool,
+        symmetry: bool,
+    ) -> Result<Vec<Event>, GameError> {
+        let (bits, _) = self.prepare(board)?;
+        self.trace = Some(Trace {
+            alpha_beta,
+            symmetry,
+            events: vec![Event::Enter(None)],
+        });
+        self.negamax(bits, cell, 0, -WIN, WIN);
+        Ok(self.trace.take().unwrap().events)
+    }
+
+    fn score_moves(&mut self, board: &Board, cell: GameCell) -> Result<Vec<(Position, i32)>, GameError> {
+        let (bits, layout) = self.prepare(board)?;
+        let mut scores = Vec::new();
+        for index in bitboard::bits(bits.moves(&layout)) {
+            let mut child = bits;
+            child.set(index, cell);
+            let score = -self.negamax(child, cell.opposite(), 1, -WIN, WIN);
+            scores.push((layout.position(index), score));
+        }
+        Ok(scores)
+    }
+
+    /// Packs `board` into a bitboard, dropping the transposition table when the board's shape changes.
+    fn prepare(&mut self, board: &Board) -> Result<(BitBoard, Arc<Layout>), GameError> {
+        if self.layout.as_ref().map(|layout| layout.config) != Some(board.config) {
+            self.table.clear();
+            self.layout = Some(Layout::get(board.config).ok_or(GameError::BoardTooLarge)?);
+            self.symmetries = symmetries(board.config);
+        }
+        let layout = self.layout.clone().unwrap();
+        let bits = board.to_bitboard().ok_or(GameError::BoardTooLarge)?;
+        Ok((bits, layout))
+    }
+
+    /// Scores are relative to the root: a win on ply `n` is worth `WIN - n`.
+    fn negamax(&mut self, board: BitBoard, cell: GameCell, ply: i32, alpha: i32, beta: i32) -> i32 {
+        let (score, bound, decided) = self.search(board, cell, ply, alpha, beta);
+        if let Some(trace) = &mut self.trace {
+            trace.events.push(Event::Leave { score, bound, decided });
+        }
+        score
+    }
+
+    /// The score of `board` for [`Solver::negamax`], how far it can be trusted, and whether it is
+    /// decided rather than estimated at the depth limit. Only a traced search keeps track of the last.
+    fn search(
+        &mut self,
+        board: BitBoard,
+        cell: GameCell,
+        ply: i32,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> (i32, Bound, bool) {
+        let layout = self.layout.clone().unwrap();
+        let score = match board.get_state(&layout) {
+            // Only the player who just moved can have completed a line. That wins for the side to
+            // move under misère rules, and in wild games whichever piece the line is made of
+            State::Win(_) if layout.config.wild && layout.config.misere => Some(WIN - ply),
+            State::Win(_) if layout.config.wild => Some(-(WIN - ply)),
+            State::Win(winner) if winner == cell => Some(WIN - ply),
+            State::Win(_) => Some(-(WIN - ply)),
+            State::Draw => Some(0),
+            State::Empty => None,
+        };
+        if let Some(score) = score {
+            return (score, Bound::Exact, true);
+        }
+
+        let depth = match self.max_depth {
+            Some(max_depth) if ply as usize >= max_depth => {
+                return (heuristic(&board, cell, &layout), Bound::Exact, false);
+            }
+            Some(max_depth) => max_depth - ply as usize,
+            None => usize::MAX,
+        };
+
+        let alpha_orig = alpha;
+        let key = self.key(&board, cell);
+        let tracing = self.trace.is_some();
+        if let Some(entry) = self.table.get(&key).filter(|entry| !tracing && entry.depth >= depth) {
+            let score = from_table(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return (score, Bound::Exact, true),
+                Bound::Lower => alpha = alpha.max(score),
+                Bound::Upper => beta = beta.min(score),
+            }
+            if alpha >= beta {
+                return (score, entry.bound, true);
+            }
+        }
+
+        let own = [cell];
+        let pieces = if layout.config.free_pieces() {
+            &WILD_PIECES[..]
+        } else {
+            &own[..]
+        };
+        let prune = self.trace.as_ref().is_none_or(|trace| trace.alpha_beta);
+        let mut seen = Vec::new();
+        let mut best = -WIN;
+        let mut decided = true;
+        'search: for index in bitboard::bits(board.moves(&layout)) {
+            for piece in pieces {
+                let mut child = board;
+                child.set(index, *piece);
+                if self.trace.as_ref().is_some_and(|trace| trace.symmetry) {
+                    let key = self.key(&child, cell.opposite());
+                    if seen.contains(&key) {
+                        continue;
+                    }
+                    seen.push(key);
+                }
+                if let Some(trace) = &mut self.trace {
+                    trace.events.push(Event::Enter(Some((layout.position(index), *piece))));
+                }
+                let score = -self.negamax(child, cell.opposite(), ply + 1, -beta, -alpha);
+                if let Some(trace) = &self.trace {
+                    decided &= matches!(trace.events.last(), Some(Event::Leave { decided: true, .. }));
+                }
+                best = best.max(score);
+                if prune {
+                    alpha = alpha.max(score);
+                }
+                if alpha >= beta {
+                    if self.trace.is_some() {
+                        let skipped = self.skipped_moves(&board, cell, pieces, (index, *piece), &mut seen);
+                        if let Some(trace) = self.trace.as_mut().filter(|_| !skipped.is_empty()) {
+                            trace.events.push(Event::Cutoff(skipped));
+                        }
+                    }
+                    break 'search;
+                }
+            }
+        }
+
+        let bound = if best <= alpha_orig {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        if tracing {
+            // A forced result found below stands whatever the unsearched positions hold
+            return (best, bound, decided || best.abs() > DECISIVE);
+        }
+        self.table.insert(
+            key,
+            Entry {
+                score: to_table(best, ply),
+                bound,
+                depth,
+            },
+        );
+        (best, bound, true)
+    }
+
+    /// The moves a cutoff after `last` left unsearched in a traced search of `board`, leaving out
+    /// those equivalent to a move already `seen` when the trace skips them.
+    fn skipped_moves(
+        &self,
+        board: &BitBoard,
+        cell: GameCell,
+        pieces: &[GameCell],
+        last: (usize, GameCell),
+        seen: &mut Vec<Key>,
+    ) -> Vec<(Position, GameCell)> {
+        let layout = self.layout.as_ref().unwrap();
+        let symmetry = self.trace.as_ref().is_some_and(|trace| trace.symmetry);
+        let mut moves = Vec::new();
+        for index in bitboard::bits(board.moves(layout)).filter(|index| *index >= last.0) {
+            let later = pieces
+                .iter()
+                .skip_while(|piece| index == last.0 && **piece != last.1)
+                .skip(usize::from(index == last.0));
+            for piece in later {
+                let mut child = *board;
+                child.set(index, *piece);
+                let key = self.key(&child, cell.opposite());
+                if symmetry && seen.contains(&key) {
+                    continue;
+                }
+                seen.push(key);
+                moves.push((layout.position(index), *piece));
+            }
+        }
+        moves
+    }
+
+    /// The smallest image of the board over all of its symmetries, paired with the side to move.
+    /// In wild games the side to move does not matter, and neither does swapping the pieces.
+    fn key(&self, board: &BitBoard, cell: GameCell) -> Key {
+        let wild = self.layout.as_ref().is_some_and(|layout| layout.config.wild);
+        let canonical = self
+            .symmetries
+            .iter()
+            .flat_map(|map| {
+                let (crosses, circles) = (permute(board.crosses, map), permute(board.circles, map));
+                [
+                    (crosses, circles),
+                    if wild { (circles, crosses) } else { (crosses, circles) },
+                ]
+            })
+            .min()
+            .unwrap_or((board.crosses, board.circles));
+        (canonical.0, canonical.1, !wild && cell == GameCell::Cross)
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+/// Solves `board` for `cell` with a fresh solver.
+pub fn solve(board: &Board, cell: GameCell) -> Result<Value, GameError> {
+    Solver::new().solve(board, cell)
+}
+
+/// Estimates an undecided position for `cell`: every line still open to one side counts for that
+/// side, more so the more pieces it already holds. Under misère rules such lines are a liability
+/// instead.
+fn heuristic(board: &BitBoard, cell: GameCell, layout: &Layout) -> i32 {
+    // Wild pieces belong to nobody, so no line favours either side
+    if layout.config.free_pieces() {
+        return 0;
+    }
+    let own = board.pieces(cell, layout);
+    let other = board.pieces(cell.opposite(), layout);
+    let mut score = 0;
+    for line in &layout.lines {
+        match ((own & line).count_ones() as i32, (other & line).count_ones() as i32) {
+            (n, 0) => score += n * n,
+            (0, n) => score -= n * n,
+            _ => {}
+        }
+    }
+    if layout.config.misere {
+        -score
+    } else {
+        score
+    }
+}
+
+/// Converts a root-relative score into one relative to the position it is stored for.
+fn to_table(score: i32, ply: i32) -> i32 {
+    match score {
+        s if s > DECISIVE => s + ply,
+        s if s < -DECISIVE => s - ply,
+        s => s,
+    }
+}
+
+fn from_table(score: i32, ply: i32) -> i32 {
+    match score {
+        s if s > DECISIVE => s - ply,
+        s if s < -DECISIVE => s + ply,
+        s => s,
+    }
+}
+
+/// Moves bit `i` of `mask` to bit `map[i]`.
+fn permute(mask: Mask, map: &[usize]) -> Mask {
+    let mut image = 0;
+    for (from, to) in map.iter().enumerate() {
+        image |= (mask >> from & 1) << to;
+    }
+    image
+}
+
+/// Index maps for every rotation and reflection that leaves the board's shape unchanged.
+///
+/// Entry `i` of a map is the index, in row-major order, that the cell at index `i` moves to.
+fn symmetries(config: BoardConfig) -> Vec<Vec<usize>> {
+    Transform::symmetries(config)
+        .into_iter()
+        .map(|transform| {
+            (0..config.width * config.height)
+                .map(|index| {
+                    let pos = Position {
+                        x: index % config.width,
+                        y: index / config.width,
+                    };
+                    let image = transform.apply(pos, config);
+                    image.y * config.width + image.x
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_rows(rows: [&str; 3]) -> Board {
+        let mut board = Board::default();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let cell = match c {
+                    'X' => GameCell::Cross,
+                    'O' => GameCell::Circle,
+                    _ => GameCell::Empty,
+                };
+                board.set_cell_force(Position { x, y }, cell);
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn test_empty_board_is_a_draw() {
+        assert_eq!(
+            solve(&Board::default(), GameCell::Cross).unwrap(),
+            Value {
+                outcome: Outcome::Draw,
+                plies: 9
+            }
+        );
+        let gomoku = Board::new(BoardConfig::gomoku());
+        assert_eq!(solve(&gomoku, GameCell::Cross), Err(GameError::BoardTooLarge));
+        assert_eq!(
+            Solver::new().solve_moves(&gomoku, GameCell::Cross),
+            Err(GameError::BoardTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_distance_to_result() {
+        let board = from_rows(["XX.", "OO.", "..."]);
+        let win_now = Value {
+            outcome: Outcome::Win,
+            plies: 1,
+        };
+        assert_eq!(solve(&board, GameCell::Cross).unwrap(), win_now);
+        assert_eq!(solve(&board, GameCell::Circle).unwrap(), win_now);
+
+        // Answering a corner opening on the adjacent edge loses to a fork
+        let edge_reply = from_rows(["X..", "O..", "..."]);
+        assert_eq!(
+            solve(&edge_reply, GameCell::Cross).unwrap(),
+            Value {
+                outcome: Outcome::Win,
+                plies: 5
+            }
+        );
+        assert_eq!(
+            solve(&from_rows(["X..", ".O.", "..."]), GameCell::Cross)
+                .unwrap()
+                .outcome,
+            Outcome::Draw
+        );
+
+        let finished = from_rows(["XXX", "OO.", "..."]);
+        assert_eq!(
+            solve(&finished, GameCell::Circle).unwrap(),
+            Value {
+                outcome: Outcome::Loss,
+                plies: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_best_move() {
+        let mut solver = Solver::new();
+        // X wins on the top row instead of blocking O's middle row
+        let board_a = from_rows(["XX.", "OO.", "X.O"]);
+        assert_eq!(solver.best_move(&board_a, GameCell::Cross), Ok(Position { x: 2, y: 0 }));
+        // O must block the top row
+        let board_b = from_rows(["XX.", ".O.", "..."]);
+        assert_eq!(
+            solver.best_move(&board_b, GameCell::Circle),
+            Ok(Position { x: 2, y: 0 })
+        );
+        let full = from_rows(["XOX", "XOO", "OXX"]);
+        assert_eq!(
+            solver.best_move(&full, GameCell::Circle),
+            Err(GameError::NoMovesAvailable)
+        );
+    }
+
+    #[test]
+    fn test_symmetric_positions_share_entries() {
+        let mut solver = Solver::new();
+        let a = from_rows(["X..", "...", "..."]);
+        let b = from_rows(["..X", "...", "..."]);
+        let (a, _) = solver.prepare(&a).unwrap();
+        let b = b.to_bitboard().unwrap();
+        assert_eq!(solver.key(&a, GameCell::Circle), solver.key(&b, GameCell::Circle));
+        assert_ne!(solver.key(&a, GameCell::Circle), solver.key(&a, GameCell::Cross));
+    }
+
+    #[test]
+    fn test_wild() {
+        let wild = |rows| {
+            let mut board = from_rows(rows);
+            board.config.wild = true;
+            board
+        };
+        // The first player wins wild tic-tac-toe, whichever piece the side to move is given
+        for cell in [GameCell::Cross, GameCell::Circle] {
+            assert_eq!(solve(&wild(["...", "...", "..."]), cell).unwrap().outcome, Outcome::Win);
+        }
+
+        let mut board = wild(["X.X", "O.O", "..."]);
+        let (pos, piece) = Solver::new().best_wild_move(&board).unwrap();
+        board.set_cell(pos, piece).unwrap();
+        assert!(matches!(board.get_state(), State::Win(_)));
+
+        // Whatever the piece, the board is won for the player who just moved
+        let finished = wild(["OOO", "XX.", "..."]);
+        assert_eq!(solve(&finished, GameCell::Circle).unwrap().outcome, Outcome::Loss);
+        assert_eq!(solve(&finished, GameCell::Cross).unwrap().outcome, Outcome::Loss);
+    }
+
+    #[test]
+    fn test_misere() {
+        let misere = |rows| {
+            let mut board = from_rows(rows);
+            board.config.misere = true;
+            board
+        };
+        assert_eq!(
+            solve(&misere(["...", "...", "..."]), GameCell::Cross).unwrap().outcome,
+            Outcome::Draw
+        );
+
+        let finished = misere(["XXX", "OO.", "..."]);
+        assert_eq!(
+            solve(&finished, GameCell::Circle).unwrap(),
+            Value {
+                outcome: Outcome::Win,
+                plies: 0
+            }
+        );
+
+        // Completing the top row would lose, so X plays anywhere else
+        let board = misere(["XX.", "OO.", "..O"]);
+        let best = Solver::new().best_move(&board, GameCell::Cross).unwrap();
+        assert_ne!(best, Position { x: 2, y: 0 });
+        assert_ne!(solve(&board, GameCell::Cross).unwrap().outcome, Outcome::Loss);
+    }
+}
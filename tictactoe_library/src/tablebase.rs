@@ -0,0 +1,300 @@
+//! Every position of classic tic-tac-toe that can come up in play, solved once.
+//!
+//! The 3x3 game has only 5,478 reachable positions (765 up to rotation and reflection), so rather
+//! than searching each time, the whole game tree is walked once, the first time the table is
+//! needed, and every position's value and best moves are stored under the position's base-3 code.
+//! Looking a position up is then a matter of indexing an array.
+//!
+//! Only the classic rules are covered: a 3x3 board, three in a row, and no misère, gravity or
+//! wild pieces.
+
+use std::sync::OnceLock;
+
+use crate::{
+    game::{Board, BoardConfig},
+    solver::{Outcome, Value},
+    update::{GameCell, Position},
+};
+
+/// Number of reachable positions, including finished ones.
+pub const POSITIONS: usize = 5_478;
+
+const SIZE: usize = 3;
+
+const CELLS: usize = SIZE * SIZE;
+
+/// Number of ways to fill the board with crosses, circles and empty cells, and so the number of
+/// base-3 codes a board can have.
+const CODES: usize = 19_683;
+
+/// The rows, columns and diagonals, as row-major cell indices.
+const LINES: [[usize; SIZE]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// What the table knows about one position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    /// The value for the side to move
+    pub value: Value,
+    /// The cells of every move that keeps `value`, as bits of their row-major index
+    best: u16,
+}
+
+impl Entry {
+    /// Every move that keeps the position's value, in row-major order. Empty once the game is over.
+    pub fn best_moves(&self) -> Vec<Position> {
+        (0..CELLS)
+            .filter(|i| self.best & (1 << i) != 0)
+            .map(|i| Position {
+                x: i % SIZE,
+                y: i / SIZE,
+            })
+            .collect()
+    }
+}
+
+/// Reachable positions indexed by their base-3 code, with `None` for the rest.
+pub struct Tablebase {
+    entries: Vec<Option<Entry>>,
+}
+
+impl Tablebase {
+    /// Builds the table by walking the whole game tree from the empty board.
+    pub fn generate() -> Tablebase {
+        let mut table = Tablebase {
+            entries: vec![None; CODES],
+        };
+        table.visit(&mut [0; CELLS]);
+        table
+    }
+
+    /// The entry for `board` with `cell` to move, or `None` if the board is not a classic 3x3
+    /// board, could not come up in play, or it is the other side's turn.
+    pub fn get(&self, board: &Board, cell: GameCell) -> Option<Entry> {
+        if board.config != BoardConfig::default() {
+            return None;
+        }
+        let mut cells = [0; CELLS];
+        for (i, cell) in board.cells.iter().flatten().enumerate() {
+            cells[i] = digit(*cell);
+        }
+        if digit(cell) != to_move(&cells) {
+            return None;
+        }
+        self.entries[code(&cells)]
+    }
+
+    /// The number of positions in the table.
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every position in the table with its entry.
+    pub fn positions(&self) -> impl Iterator<Item = (Board, Entry)> + '_ {
+        self.entries.iter().enumerate().filter_map(|(code, entry)| {
+            let mut board = Board::default();
+            let mut rest = code;
+            for i in 0..CELLS {
+                let cell = match rest % 3 {
+                    1 => GameCell::Cross,
+                    2 => GameCell::Circle,
+                    _ => GameCell::Empty,
+                };
+                board.set_cell_force(
+                    Position {
+                        x: i % SIZE,
+                        y: i / SIZE,
+                    },
+                    cell,
+                );
+                rest /= 3;
+            }
+            entry.map(|entry| (board, entry))
+        })
+    }
+
+    /// Solves `cells` and everything reachable from it, returning its value for the side to move.
+    fn visit(&mut self, cells: &mut [u8; CELLS]) -> Value {
+        if let Some(entry) = self.entries[code(cells)] {
+            return entry.value;
+        }
+        let mut entry = Entry {
+            value: Value {
+                outcome: Outcome::Draw,
+                plies: 0,
+            },
+            best: 0,
+        };
+        // Only the player who just moved can have completed a line
+        if LINES
+            .iter()
+            .any(|line| cells[line[0]] != 0 && line.iter().all(|i| cells[*i] == cells[line[0]]))
+        {
+            entry.value.outcome = Outcome::Loss;
+        } else if cells.contains(&0) {
+            let mover = to_move(cells);
+            let mut best: Option<Value> = None;
+            for i in 0..CELLS {
+                if cells[i] != 0 {
+                    continue;
+                }
+                cells[i] = mover;
+                let value = self.visit(cells).before_move();
+                cells[i] = 0;
+                if best.is_none_or(|best| value > best) {
+                    best = Some(value);
+                    entry.best = 0;
+                }
+                if best == Some(value) {
+                    entry.best |= 1 << i;
+                }
+            }
+            entry.value = best.unwrap();
+        }
+        self.entries[code(cells)] = Some(entry);
+        entry.value
+    }
+}
+
+/// The table, generated the first time it is needed.
+pub fn tablebase() -> &'static Tablebase {
+    static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+    TABLEBASE.get_or_init(Tablebase::generate)
+}
+
+/// Looks up `board` with `cell` to move in the [`tablebase`].
+pub fn lookup(board: &Board, cell: GameCell) -> Option<Entry> {
+    tablebase().get(board, cell)
+}
+
+fn digit(cell: GameCell) -> u8 {
+    match cell {
+        GameCell::Empty => 0,
+        GameCell::Cross => 1,
+        GameCell::Circle => 2,
+    }
+}
+
+/// The base-3 code of `cells`, with the first cell as the lowest digit.
+fn code(cells: &[u8; CELLS]) -> usize {
+    cells.iter().rev().fold(0, |code, digit| code * 3 + *digit as usize)
+}
+
+/// The digit of the side to move, counting the pieces placed: crosses always start.
+fn to_move(cells: &[u8; CELLS]) -> u8 {
+    if cells.iter().filter(|digit| **digit != 0).count() % 2 == 0 {
+        1
+    } else {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{game::State, solver::Solver};
+
+    /// Plain minimax over the whole tree below `board`, with no table or pruning, checking the
+    /// table's value and best moves at every node. Returns the value for `cell`, the side to move.
+    fn brute_force(board: &mut Board, cell: GameCell, table: &Tablebase, checked: &mut usize) -> Value {
+        let state = board.get_state();
+        let mut best: Option<(Value, Vec<Position>)> = None;
+        if state == State::Empty {
+            for pos in board.available_moves() {
+                board.set_cell_force(pos, cell);
+                let value = brute_force(board, cell.opposite(), table, checked).before_move();
+                board.set_cell_force(pos, GameCell::Empty);
+                match &mut best {
+                    Some((best, _)) if value < *best => {}
+                    Some((best, moves)) if value == *best => moves.push(pos),
+                    _ => best = Some((value, vec![pos])),
+                }
+            }
+        }
+        let (value, moves) = best.unwrap_or((
+            Value {
+                outcome: if state == State::Draw {
+                    Outcome::Draw
+                } else {
+                    Outcome::Loss
+                },
+                plies: 0,
+            },
+            Vec::new(),
+        ));
+        let entry = table.get(board, cell).unwrap();
+        assert_eq!((entry.value, entry.best_moves()), (value, moves), "{}", board);
+        *checked += 1;
+        value
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let table = Tablebase::generate();
+        let mut checked = 0;
+        brute_force(&mut Board::default(), GameCell::Cross, &table, &mut checked);
+        // Every node of the game tree, so positions reached by several orders of moves more than once
+        assert_eq!(checked, 549_946);
+        assert_eq!(
+            table.get(&Board::default(), GameCell::Cross).unwrap().value,
+            Value {
+                outcome: Outcome::Draw,
+                plies: 9
+            }
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let table = tablebase();
+        assert_eq!(table.len(), POSITIONS);
+        let canonical = table
+            .positions()
+            .map(|(board, _)| board.canonical().0.cells)
+            .collect::<HashSet<_>>();
+        assert_eq!(canonical.len(), 765);
+
+        // The solver agrees on every position
+        let mut solver = Solver::new();
+        for (board, entry) in table.positions() {
+            let cell = if board.moves() % 2 == 0 {
+                GameCell::Cross
+            } else {
+                GameCell::Circle
+            };
+            assert_eq!(solver.solve(&board, cell), Ok(entry.value));
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let board: Board = "XX./OO./...".parse().unwrap();
+        let entry = lookup(&board, GameCell::Cross).unwrap();
+        assert_eq!(entry.best_moves(), vec![Position { x: 2, y: 0 }]);
+        assert_eq!(lookup(&board, GameCell::Circle), None);
+
+        // Three crosses and no circles never comes up in play
+        let unreachable: Board = "XXX/.../...".parse().unwrap();
+        assert_eq!(lookup(&unreachable, GameCell::Circle), None);
+        let mut misere = Board::default();
+        misere.config.misere = true;
+        assert_eq!(lookup(&misere, GameCell::Cross), None);
+
+        let finished: Board = "XXX/OO./...".parse().unwrap();
+        assert!(lookup(&finished, GameCell::Circle).unwrap().best_moves().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Counting the whole game tree below a position.
+//!
+//! [`statistics`] plays out every possible continuation of a position and reports how many games
+//! there are, how they end and on which ply, and how many distinct positions come up along the
+//! way. From the empty classic board these are well known, so they double as a check on
+//! [`Board::get_state`] and [`Board::available_moves`].
+//!
+//! Positions reached by several orders of moves are only expanded once, so the walk is as cheap
+//! as the number of positions rather than the number of games.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::AddAssign,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{Board, Cells, Player, State},
+    update::GameCell,
+};
+
+/// How a number of games ended.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Results {
+    pub player1: u64,
+    pub player2: u64,
+    pub draws: u64,
+}
+
+impl Results {
+    pub fn games(&self) -> u64 {
+        self.player1 + self.player2 + self.draws
+    }
+}
+
+impl AddAssign for Results {
+    fn add_assign(&mut self, other: Self) {
+        self.player1 += other.player1;
+        self.player2 += other.player2;
+        self.draws += other.draws;
+    }
+}
+
+/// The counts for the game tree below a position.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeStats {
+    /// How the games that end on each ply ended, indexed by the number of plies played from the
+    /// starting position
+    pub by_ply: Vec<Results>,
+    /// Distinct positions that come up, counting the starting position and finished ones
+    pub positions: usize,
+    /// Distinct positions when rotations and reflections of a position count as the same one
+    pub symmetric_positions: usize,
+}
+
+impl TreeStats {
+    /// How all the games ended.
+    pub fn results(&self) -> Results {
+        let mut results = Results::default();
+        for ply in &self.by_ply {
+            results += *ply;
+        }
+        results
+    }
+
+    /// The number of different games that can be played from the starting position.
+    pub fn games(&self) -> u64 {
+        self.results().games()
+    }
+}
+
+/// Walks every game that can be played from `board` with `to_move` to play. In wild games and
+/// Order and Chaos each move branches on both pieces.
+///
+/// The tree grows very quickly with the size of the board, so this is only practical for boards
+/// of about nine or ten cells; a position further into the game can be walked on larger boards.
+pub fn statistics(board: &Board, to_move: Player) -> TreeStats {
+    let mut seen = HashMap::new();
+    let by_ply = walk(&mut board.clone(), to_move, &mut seen);
+    let symmetric_positions = seen
+        .keys()
+        .map(|cells| {
+            let board = Board {
+                cells: cells.clone(),
+                config: board.config,
+            };
+            board.canonical().0.cells
+        })
+        .collect::<HashSet<_>>()
+        .len();
+    TreeStats {
+        by_ply,
+        positions: seen.len(),
+        symmetric_positions,
+    }
+}
+
+/// The results of the games below `board`, by ply, remembering them for every position in `seen`.
+/// A position's cells are enough to tell it apart, as the side to move follows from the number
+/// of moves played since the start.
+fn walk(board: &mut Board, to_move: Player, seen: &mut HashMap<Cells, Vec<Results>>) -> Vec<Results> {
+    if let Some(by_ply) = seen.get(&board.cells) {
+        return by_ply.clone();
+    }
+    let mut by_ply = vec![Results::default()];
+    match board.get_state() {
+        State::Win(cell) => match board.config.winner(cell, to_move.next()) {
+            Some(Player::Player1) => by_ply[0].player1 = 1,
+            Some(Player::Player2) => by_ply[0].player2 = 1,
+            None => by_ply[0].draws = 1,
+        },
+        State::Draw => by_ply[0].draws = 1,
+        State::Empty => {
+            let own = [to_move.get_cell()];
+            let pieces = if board.config.free_pieces() {
+                &[GameCell::Cross, GameCell::Circle][..]
+            } else {
+                &own[..]
+            };
+            for pos in board.available_moves() {
+                for piece in pieces {
+                    board.set_cell_force(pos, *piece);
+                    let below = walk(board, to_move.next(), seen);
+                    board.set_cell_force(pos, GameCell::Empty);
+                    if by_ply.len() < below.len() + 1 {
+                        by_ply.resize(below.len() + 1, Results::default());
+                    }
+                    for (ply, results) in below.into_iter().enumerate() {
+                        by_ply[ply + 1] += results;
+                    }
+                }
+            }
+        }
+    }
+    seen.insert(board.cells.clone(), by_ply.clone());
+    by_ply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{BoardConfig, Role},
+        update::Position,
+    };
+
+    #[test]
+    fn test_classic() {
+        let stats = statistics(&Board::default(), Player::Player1);
+        assert_eq!(stats.games(), 255_168);
+        assert_eq!(
+            stats.results(),
+            Results {
+                player1: 131_184,
+                player2: 77_904,
+                draws: 46_080
+            }
+        );
+        let wins = |ply: usize| (stats.by_ply[ply].player1, stats.by_ply[ply].player2);
+        assert_eq!(
+            (wins(5), wins(6), wins(7), wins(8), wins(9)),
+            ((1_440, 0), (0, 5_328), (47_952, 0), (0, 72_576), (81_792, 0))
+        );
+        assert_eq!(stats.by_ply[9].draws, 46_080);
+        assert_eq!(stats.by_ply[..5].iter().map(Results::games).sum::<u64>(), 0);
+        assert_eq!(stats.positions, 5_478);
+        assert_eq!(stats.symmetric_positions, 765);
+    }
+
+    #[test]
+    fn test_from_position() {
+        // The games after each first move add up to all games, with every ply one earlier
+        let classic = statistics(&Board::default(), Player::Player1);
+        let mut by_ply = vec![Results::default(); classic.by_ply.len()];
+        for pos in Board::default().available_moves() {
+            let mut board = Board::default();
+            board.set_cell_force(pos, GameCell::Cross);
+            let stats = statistics(&board, Player::Player2);
+            assert_eq!(stats.by_ply.len(), 9);
+            for (ply, results) in stats.by_ply.into_iter().enumerate() {
+                by_ply[ply + 1] += results;
+            }
+        }
+        assert_eq!(by_ply, classic.by_ply);
+
+        let finished: Board = "XXX/OO./...".parse().unwrap();
+        let stats = statistics(&finished, Player::Player2);
+        assert_eq!(
+            stats.by_ply,
+            vec![Results {
+                player1: 1,
+                player2: 0,
+                draws: 0
+            }]
+        );
+        assert_eq!(stats.positions, 1);
+    }
+
+    #[test]
+    fn test_rules() {
+        let classic = statistics(&Board::default(), Player::Player1);
+        // Under misère rules the same games end the same way, but the other player wins
+        let misere = BoardConfig {
+            misere: true,
+            ..BoardConfig::default()
+        };
+        let stats = statistics(&Board::new(misere), Player::Player1);
+        let results = classic.results();
+        assert_eq!(
+            stats.results(),
+            Results {
+                player1: results.player2,
+                player2: results.player1,
+                draws: results.draws
+            }
+        );
+
+        // Under gravity each move picks one of the three columns, and only the mirror image is left
+        // as a symmetry. The counts match a plain enumeration of the column choices.
+        let gravity = BoardConfig {
+            gravity: true,
+            ..BoardConfig::new(3, 3, 3).unwrap()
+        };
+        let stats = statistics(&Board::new(gravity), Player::Player1);
+        assert_eq!(
+            stats.results(),
+            Results {
+                player1: 616,
+                player2: 386,
+                draws: 308
+            }
+        );
+        assert_eq!(stats.games(), 1_310);
+        assert_eq!(stats.positions, 694);
+        assert_eq!(stats.symmetric_positions, 359);
+
+        // Wild games branch on both pieces, and the first player to complete a line wins
+        let wild = BoardConfig {
+            wild: true,
+            ..BoardConfig::new(2, 2, 2).unwrap()
+        };
+        let stats = statistics(&Board::new(wild), Player::Player1);
+        // Any two equal pieces on a 2x2 board make a line, so the second or third move ends it
+        assert_eq!(stats.by_ply[2].player2, 8 * 3);
+        assert_eq!(stats.by_ply[3].player1, 8 * 3 * 2 * 2);
+        assert_eq!(stats.games(), 8 * 3 + 8 * 3 * 2 * 2);
+
+        // A full Order and Chaos board without a line wins for Chaos, here Player 1
+        let mut board = Board::new(BoardConfig::order_and_chaos(Role::Chaos));
+        for y in 0..6 {
+            for x in 0..6 {
+                let cell = if (x + 2 * y) % 4 < 2 {
+                    GameCell::Cross
+                } else {
+                    GameCell::Circle
+                };
+                board.set_cell_force(Position { x, y }, cell);
+            }
+        }
+        board.set_cell_force(Position { x: 5, y: 5 }, GameCell::Empty);
+        let stats = statistics(&board, Player::Player2);
+        // Neither piece in the last cell makes five in a row
+        assert_eq!(stats.games(), 2);
+        assert_eq!(stats.by_ply[1].player1, 2);
+    }
+}
@@ -0,0 +1,440 @@
+//! Ultimate tic-tac-toe: nine small boards arranged in a big one.
+//!
+//! The cell a player picks inside a small board sends their opponent to the small board in the
+//! same place on the big board. If that board has already been won or drawn, the opponent may
+//! play in any open board. Winning a small board claims its cell on the big board, and three
+//! claimed cells in a row win the game.
+//!
+//! Positions cover the whole 9x9 grid, so `(4, 4)` is the centre of the centre board.
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::GameError,
+    game::{Board, Player, Ply, State},
+    mcts::Mcts,
+    search::Searchable,
+    solver::Outcome,
+    update::{Difficulty, GameCell, Move, Opponent, Position},
+};
+
+/// Cells along each side of the whole board.
+pub const SIZE: usize = 9;
+
+/// Cells along each side of a small board, and small boards along each side of the big one.
+const SMALL: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UltimateBoard {
+    /// The nine small boards, row by row
+    pub boards: Vec<Board>,
+    /// The big board, holding the winner of each small board
+    pub meta: Board,
+    /// The small board the next move has to be played in, or `None` if any open board will do
+    pub forced: Option<usize>,
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        UltimateBoard {
+            boards: vec![Board::default(); SMALL * SMALL],
+            meta: Board::default(),
+            forced: None,
+        }
+    }
+}
+
+impl UltimateBoard {
+    /// The index of the small board holding `pos`.
+    pub fn board_index(pos: Position) -> usize {
+        pos.y / SMALL * SMALL + pos.x / SMALL
+    }
+
+    /// The position of `pos` inside its small board.
+    fn inner(pos: Position) -> Position {
+        Position {
+            x: pos.x % SMALL,
+            y: pos.y % SMALL,
+        }
+    }
+
+    /// The cell of the big board that stands for the small board `index`.
+    fn meta_position(index: usize) -> Position {
+        Position {
+            x: index % SMALL,
+            y: index / SMALL,
+        }
+    }
+
+    pub fn get_cell(&self, pos: Position) -> Option<&GameCell> {
+        if pos.x >= SIZE || pos.y >= SIZE {
+            return None;
+        }
+        self.boards[UltimateBoard::board_index(pos)].get_cell(UltimateBoard::inner(pos))
+    }
+
+    /// Whether the small board `index` can still be played in.
+    pub fn is_open(&self, index: usize) -> bool {
+        self.boards[index].get_state() == State::Empty
+    }
+
+    /// Whether a move may be made in the small board `index` right now.
+    pub fn is_playable(&self, index: usize) -> bool {
+        self.forced.is_none_or(|forced| forced == index) && self.is_open(index)
+    }
+
+    /// Every legal move, row by row across the whole grid.
+    pub fn available_moves(&self) -> Vec<Position> {
+        if self.get_state() != State::Empty {
+            return Vec::new();
+        }
+        let playable = (0..self.boards.len())
+            .map(|index| self.is_playable(index))
+            .collect::<Vec<_>>();
+        let mut moves = Vec::new();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let pos = Position { x, y };
+                if playable[UltimateBoard::board_index(pos)] && self.get_cell(pos) == Some(&GameCell::Empty) {
+                    moves.push(pos);
+                }
+            }
+        }
+        moves
+    }
+
+    /// Plays `cell` at `pos`, claims the small board if that wins it, and picks the board the
+    /// opponent has to play in next.
+    pub fn set_cell(&mut self, pos: Position, cell: GameCell) -> Result<(), GameError> {
+        if pos.x >= SIZE || pos.y >= SIZE {
+            return Err(GameError::OutOfBounds(pos));
+        }
+        if self.get_state() != State::Empty {
+            return Err(GameError::GameOver);
+        }
+        let index = UltimateBoard::board_index(pos);
+        if !self.is_playable(index) {
+            return Err(GameError::WrongBoard(pos));
+        }
+        let inner = UltimateBoard::inner(pos);
+        self.boards[index]
+            .set_cell(inner, cell)
+            .map_err(|_| GameError::CellOccupied(pos))?;
+        if let State::Win(winner) = self.boards[index].get_state() {
+            self.meta.set_cell_force(UltimateBoard::meta_position(index), winner);
+        }
+        let target = inner.y * SMALL + inner.x;
+        self.forced = if self.is_open(target) { Some(target) } else { None };
+        Ok(())
+    }
+
+    /// Won once the big board holds a line. Drawn once every small board is decided without one.
+    pub fn get_state(&self) -> State {
+        match self.meta.get_state() {
+            State::Win(cell) => State::Win(cell),
+            _ if (0..self.boards.len()).all(|index| !self.is_open(index)) => State::Draw,
+            _ => State::Empty,
+        }
+    }
+}
+
+/// An Ultimate board together with the piece that will be placed next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UltimateTurn {
+    pub board: UltimateBoard,
+    pub cell: GameCell,
+}
+
+impl Searchable for UltimateTurn {
+    type Action = Position;
+
+    fn actions(&self) -> Vec<Position> {
+        self.board.available_moves()
+    }
+
+    fn play(&mut self, pos: Position) {
+        // Only ever called with one of `actions`, which are all legal
+        let _ = self.board.set_cell(pos, self.cell);
+        self.cell = self.cell.opposite();
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.board.get_state() {
+            State::Win(_) => Some(Outcome::Loss),
+            State::Draw => Some(Outcome::Draw),
+            State::Empty => None,
+        }
+    }
+}
+
+/// The move the computer playing as `opponent` picks for `cell`. Ultimate is too large to solve,
+/// so the stronger opponents search it with [`Mcts`].
+pub fn get_pos(opponent: Opponent, board: &UltimateBoard, cell: GameCell) -> Result<Position, GameError> {
+    let iterations = match opponent {
+        Opponent::Random => {
+            return board
+                .available_moves()
+                .choose(&mut rand::thread_rng())
+                .copied()
+                .ok_or(GameError::NoMovesAvailable)
+        }
+        Opponent::Minimax => Difficulty::Perfect.iterations(),
+        Opponent::Computer(difficulty) => difficulty.iterations(),
+        Opponent::Human | Opponent::Online | Opponent::Custom => return Err(GameError::OpponentCannotMove(opponent)),
+    };
+    let turn = UltimateTurn {
+        board: board.clone(),
+        cell,
+    };
+    Mcts::new(iterations, 1.4)
+        .search(&turn)
+        .ok_or(GameError::NoMovesAvailable)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UltimateState {
+    GameOver(Option<Player>, UltimateBoard),
+    GameInProgress(UltimateBoard, Player, Position),
+}
+
+/// A game of Ultimate tic-tac-toe, driven the same way as a [`Game`](crate::game::Game).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UltimateGame {
+    pub board: UltimateBoard,
+    pub current_position: Position,
+    pub current_player: Player,
+    pub winner: Option<Player>,
+    pub opponent: Opponent,
+    should_continue: bool,
+    state_changed: bool,
+    #[serde(default)]
+    history: Vec<Ply>,
+}
+
+impl UltimateGame {
+    /// A new game against `opponent`. Only the built-in opponents play Ultimate, so
+    /// [`Opponent::Custom`] is refused with [`GameError::OpponentCannotMove`].
+    pub fn new(opponent: Opponent) -> Result<UltimateGame, GameError> {
+        if opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(opponent));
+        }
+        Ok(UltimateGame {
+            board: UltimateBoard::default(),
+            current_position: Position { x: 4, y: 4 },
+            current_player: Player::Player1,
+            winner: None,
+            opponent,
+            should_continue: true,
+            state_changed: true,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, mov: Move) -> Result<UltimateState, GameError> {
+        let pos = self.current_position;
+        match mov {
+            Move::Up if pos.y > 0 => self.current_position.y -= 1,
+            Move::Down if pos.y + 1 < SIZE => self.current_position.y += 1,
+            Move::Left if pos.x > 0 => self.current_position.x -= 1,
+            Move::Right if pos.x + 1 < SIZE => self.current_position.x += 1,
+            Move::Up | Move::Down | Move::Left | Move::Right => return Err(GameError::CannotMove(mov)),
+            Move::Place => self.play(pos)?,
+            Move::PlaceAt(pos) => self.play(pos)?,
+            Move::PlaceWith(cell) | Move::PlaceAtWith(_, cell) if cell != self.current_player.get_cell() => {
+                return Err(GameError::WrongPiece(cell))
+            }
+            Move::PlaceWith(_) => self.play(pos)?,
+            Move::PlaceAtWith(pos, _) => self.play(pos)?,
+            Move::PlaceNumber(number) | Move::PlaceNumberAt(_, number) => return Err(GameError::WrongNumber(number)),
+            Move::LayerUp | Move::LayerDown | Move::PlaceAt3(_) => return Err(GameError::CannotMove(mov)),
+        }
+        self.state_changed = true;
+        Ok(self.get_state().unwrap())
+    }
+
+    /// Places the current player's piece at `pos` and lets the computer reply if it is its turn.
+    fn play(&mut self, pos: Position) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        // A deserialised game can still name a custom opponent, which could not reply
+        if self.opponent == Opponent::Custom {
+            return Err(GameError::OpponentCannotMove(self.opponent));
+        }
+        self.board.set_cell(pos, self.current_player.get_cell())?;
+        self.history.push(Ply {
+            player: self.current_player,
+            position: pos,
+            cell: self.current_player.get_cell(),
+        });
+        match self.board.get_state() {
+            State::Empty => self.current_player = self.current_player.next(),
+            State::Win(_) => {
+                self.winner = Some(self.current_player);
+                self.should_continue = false;
+            }
+            State::Draw => self.should_continue = false,
+        }
+        self.state_changed = true;
+        let computer = !matches!(self.opponent, Opponent::Human | Opponent::Online);
+        if self.should_continue && computer && self.current_player == Player::Player2 {
+            let pos = get_pos(self.opponent, &self.board, self.current_player.get_cell())?;
+            return self.play(pos);
+        }
+        Ok(())
+    }
+
+    /// The pieces placed so far, in the order they were played.
+    pub fn history(&self) -> &[Ply] {
+        &self.history
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.should_continue
+    }
+
+    pub fn get_state(&mut self) -> Option<UltimateState> {
+        if !self.state_changed {
+            return None;
+        }
+        self.state_changed = false;
+        if self.is_over() {
+            Some(UltimateState::GameOver(self.winner, self.board.clone()))
+        } else {
+            Some(UltimateState::GameInProgress(
+                self.board.clone(),
+                self.current_player,
+                self.current_position,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forced_board() {
+        let mut board = UltimateBoard::default();
+        assert_eq!(board.available_moves().len(), 81);
+
+        // The top-right cell of the centre board sends the opponent to the top-right board
+        board.set_cell(Position { x: 5, y: 3 }, GameCell::Cross).unwrap();
+        assert_eq!(board.forced, Some(2));
+        assert_eq!(board.available_moves().len(), 9);
+        assert_eq!(
+            board.set_cell(Position { x: 0, y: 0 }, GameCell::Circle),
+            Err(GameError::WrongBoard(Position { x: 0, y: 0 }))
+        );
+        board.set_cell(Position { x: 7, y: 1 }, GameCell::Circle).unwrap();
+        assert_eq!(board.forced, Some(4));
+        assert_eq!(
+            board.set_cell(Position { x: 5, y: 3 }, GameCell::Cross),
+            Err(GameError::CellOccupied(Position { x: 5, y: 3 }))
+        );
+        assert_eq!(
+            board.set_cell(Position { x: 9, y: 3 }, GameCell::Cross),
+            Err(GameError::OutOfBounds(Position { x: 9, y: 3 }))
+        );
+    }
+
+    #[test]
+    fn test_free_choice_when_target_is_decided() {
+        let mut board = UltimateBoard::default();
+        for x in 0..3 {
+            board.boards[0].set_cell_force(Position { x, y: 0 }, GameCell::Cross);
+        }
+        board.meta.set_cell_force(Position { x: 0, y: 0 }, GameCell::Cross);
+
+        // The top-left cell of the centre board points at the board that is already won
+        board.set_cell(Position { x: 3, y: 3 }, GameCell::Circle).unwrap();
+        assert_eq!(board.forced, None);
+        assert_eq!(board.available_moves().len(), 81 - 9 - 1);
+        assert!(!board
+            .available_moves()
+            .iter()
+            .any(|pos| UltimateBoard::board_index(*pos) == 0));
+    }
+
+    #[test]
+    fn test_meta_win() {
+        let mut board = UltimateBoard::default();
+        for index in [0, 4] {
+            for x in 0..3 {
+                board.boards[index].set_cell_force(Position { x, y: 0 }, GameCell::Circle);
+            }
+            board
+                .meta
+                .set_cell_force(UltimateBoard::meta_position(index), GameCell::Circle);
+        }
+        board.boards[8].set_cell_force(Position { x: 0, y: 0 }, GameCell::Circle);
+        board.boards[8].set_cell_force(Position { x: 1, y: 0 }, GameCell::Circle);
+        board.forced = Some(8);
+        assert_eq!(board.get_state(), State::Empty);
+
+        board.set_cell(Position { x: 8, y: 6 }, GameCell::Circle).unwrap();
+        assert_eq!(board.get_state(), State::Win(GameCell::Circle));
+        assert!(board.available_moves().is_empty());
+        assert_eq!(
+            board.set_cell(Position { x: 0, y: 8 }, GameCell::Cross),
+            Err(GameError::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut game = UltimateGame::new(Opponent::Human).unwrap();
+        for _ in 0..4 {
+            game.update(Move::Right).unwrap();
+        }
+        assert_eq!(game.update(Move::Right), Err(GameError::CannotMove(Move::Right)));
+        game.update(Move::Place).unwrap();
+        assert_eq!(game.board.get_cell(Position { x: 8, y: 4 }), Some(&GameCell::Cross));
+        assert_eq!(game.board.forced, Some(5));
+        assert_eq!(game.current_player, Player::Player2);
+    }
+
+    #[test]
+    fn test_game_against_computer() {
+        let mut game = UltimateGame::new(Opponent::Computer(Difficulty::Easy)).unwrap();
+        while !game.is_over() {
+            let pos = *game.board.available_moves().choose(&mut rand::thread_rng()).unwrap();
+            game.update(Move::PlaceAt(pos)).unwrap();
+            assert!(game.is_over() || game.current_player == Player::Player1);
+        }
+        match game.board.get_state() {
+            State::Win(_) => assert_eq!(game.winner, game.history().last().map(|ply| ply.player)),
+            state => assert_eq!(state, State::Draw),
+        }
+        assert_eq!(
+            UltimateGame::new(Opponent::Custom),
+            Err(GameError::OpponentCannotMove(Opponent::Custom))
+        );
+    }
+
+    #[test]
+    fn test_mcts_takes_the_game() {
+        let mut board = UltimateBoard::default();
+        for index in [0, 4] {
+            for x in 0..3 {
+                board.boards[index].set_cell_force(Position { x, y: 0 }, GameCell::Cross);
+            }
+            board
+                .meta
+                .set_cell_force(UltimateBoard::meta_position(index), GameCell::Cross);
+        }
+        board.boards[8].set_cell_force(Position { x: 0, y: 2 }, GameCell::Cross);
+        board.boards[8].set_cell_force(Position { x: 1, y: 2 }, GameCell::Cross);
+        board.forced = Some(8);
+        let turn = UltimateTurn {
+            board,
+            cell: GameCell::Cross,
+        };
+        assert_eq!(
+            Mcts::with_seed(500, 1.4, 3).search(&turn),
+            Some(Position { x: 8, y: 8 })
+        );
+    }
+}
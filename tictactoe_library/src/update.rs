@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::ops::AddAssign;
+use std::ops::{AddAssign, SubAssign};
 // pub enum Key {
 //     Char(char),
 //     Enter,
@@ -11,7 +11,7 @@ use std::ops::AddAssign;
 //     Unknown,
 // }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 pub enum Move {
     Down,
     Up,
@@ -19,6 +19,20 @@ pub enum Move {
     Right,
     Place,
     PlaceAt(Position),
+    /// Places this piece at the cursor. Only wild games allow the other player's piece
+    PlaceWith(GameCell),
+    /// Places this piece at a position. Only wild games allow the other player's piece
+    PlaceAtWith(Position, GameCell),
+    /// Places this number at the cursor in numerical tic-tac-toe
+    PlaceNumber(u8),
+    /// Places this number at a position in numerical tic-tac-toe
+    PlaceNumberAt(Position, u8),
+    /// Moves the cursor to the layer above on a three-dimensional board
+    LayerUp,
+    /// Moves the cursor to the layer below on a three-dimensional board
+    LayerDown,
+    /// Places the player's piece at a position on a three-dimensional board
+    PlaceAt3(Position3),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -33,6 +47,22 @@ impl Position {
     }
 }
 
+/// A cell of a three-dimensional board: `x` and `y` within a layer, and `z` the layer, counting
+/// down from the top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position3 {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+impl Position3 {
+    /// The position of this cell within its layer.
+    pub fn flat(&self) -> Position {
+        Position { x: self.x, y: self.y }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Score {
     pub player1: u32,
@@ -52,6 +82,13 @@ impl AddAssign for Score {
     }
 }
 
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Score) {
+        self.player1 -= other.player1;
+        self.player2 -= other.player2;
+    }
+}
+
 impl Position {
     pub fn to_tuple(&self) -> (usize, usize) {
         (self.y, self.x)
@@ -66,6 +103,10 @@ pub enum Action {
     // Input(String),
     Quit,
     Reset,
+    Undo,
+    Redo,
+    /// Suggest the best move for the player to move
+    Hint,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -74,10 +115,54 @@ pub enum Opponent {
     Human,
     Random,
     Minimax,
+    Computer(Difficulty),
+    /// Played by a [`Strategy`](crate::player::Strategy) given to [`Game::new`](crate::game::Game::new)
+    Custom,
     // Online(OnlinePlayer)
 }
 
-#[derive(Clone, PartialEq, Copy, Debug, Serialize, Deserialize)]
+/// How strongly a computer opponent plays.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Perfect,
+}
+
+impl Difficulty {
+    /// How many plies ahead the computer looks, `None` for the whole game.
+    pub fn depth(&self) -> Option<usize> {
+        match self {
+            Difficulty::Easy => Some(1),
+            Difficulty::Medium => Some(2),
+            Difficulty::Hard => Some(4),
+            Difficulty::Perfect => None,
+        }
+    }
+
+    /// The chance of playing a random move instead of the best one found.
+    pub fn blunder_chance(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Medium => 0.25,
+            Difficulty::Hard => 0.05,
+            Difficulty::Perfect => 0.0,
+        }
+    }
+
+    /// How many playouts a Monte Carlo search runs per move, for games too large to solve.
+    pub fn iterations(&self) -> usize {
+        match self {
+            Difficulty::Easy => 100,
+            Difficulty::Medium => 1_000,
+            Difficulty::Hard => 5_000,
+            Difficulty::Perfect => 20_000,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Debug, Serialize, Deserialize)]
 pub enum GameCell {
     Empty,
     Cross,
@@ -95,27 +180,12 @@ impl GameCell {
 }
 
 impl GameCell {
-    pub fn to_text(&self, pos: Option<(usize, usize)>) -> String {
-        let centre = match self {
+    // TODO: Print proper positions with borders
+    pub fn to_text(&self, _pos: Option<(usize, usize)>) -> String {
+        match self {
             GameCell::Empty => String::from("L"),
             GameCell::Cross => String::from("X"),
             GameCell::Circle => String::from("O"),
-        };
-        // TODO: Print proper positions with borders
-        match pos {
-            Some((x, y)) => match (x, y) {
-                (0, 0) => centre,
-                (0, 1) => centre,
-                (0, 2) => centre,
-                (1, 0) => centre,
-                (1, 1) => centre,
-                (1, 2) => centre,
-                (2, 0) => centre,
-                (2, 1) => centre,
-                (2, 2) => centre,
-                _ => panic!("Invalid coordinates: {}, {}", x, y),
-            },
-            None => centre,
         }
     }
 }
@@ -10,7 +10,7 @@ use std::{
 };
 use tictactoe_library::{
     app::{App, AppState},
-    update::{Action, Move},
+    update::{Action, GameCell, Move},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
@@ -47,6 +47,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(250);
     loop {
+        app.poll_hint();
         terminal.draw(|f| ui::draw(f, &mut app))?;
 
         let timeout = tick_rate
@@ -59,7 +60,13 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                         'q' => Action::Quit,
                         'r' => Action::Reset,
                         'p' => Action::Move(Move::Place),
+                        'x' => Action::Move(Move::PlaceWith(GameCell::Cross)),
+                        'o' => Action::Move(Move::PlaceWith(GameCell::Circle)),
                         'm' => Action::ToggleMenu,
+                        'u' => Action::Undo,
+                        'y' => Action::Redo,
+                        'h' => Action::Hint,
+                        c @ '1'..='9' => Action::Move(Move::PlaceNumber(c as u8 - b'0')),
                         _ => continue,
                     },
                     event::KeyCode::Esc => Action::ToggleMenu,
@@ -68,6 +75,8 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                     event::KeyCode::Up => Action::Move(Move::Up),
                     event::KeyCode::Left => Action::Move(Move::Left),
                     event::KeyCode::Right => Action::Move(Move::Right),
+                    event::KeyCode::PageUp => Action::Move(Move::LayerUp),
+                    event::KeyCode::PageDown => Action::Move(Move::LayerDown),
                     _ => continue,
                 };
                 app.update(key);
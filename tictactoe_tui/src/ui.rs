@@ -1,7 +1,9 @@
 use tictactoe_library::{
     app::{App, AppState, Menu},
-    game::{Cells, GameState, Player},
-    update::{Position, GameCell},
+    game::{Cells, GameState, Line, Player},
+    numerical::{self, NumberCell, NumericalBoard, NumericalState},
+    qubic::{self, QubicBoard, QubicState},
+    update::{GameCell, Position, Position3},
 };
 use tui::{
     backend::Backend,
@@ -23,7 +25,7 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let state = &app.state;
     match state {
         AppState::Menu(menu, row) => match menu {
-            Menu::Start => draw_start_menu(f, *row as usize),
+            Menu::Start => draw_start_menu(f, *row as usize, app),
             Menu::Game => {
                 let mut menu = Layout::default()
                     .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
@@ -35,10 +37,10 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         AppState::Playing(game_state) => {
             match game_state {
                 GameState::GameInProgress(cells, _, pos) => {
-                    draw_board(f, cells.to_vec(), pos.clone(), &main[0]);
+                    draw_board(f, cells.to_vec(), Some(*pos), &main[0]);
                 }
-                GameState::GameOver(winner, cells) => {
-                    draw_game_over(f, &main[0], *winner, cells.clone());
+                GameState::GameOver(winner, cells, lines) => {
+                    draw_game_over(f, &main[0], *winner, cells.clone(), lines);
                 }
             }
             match &app.warning_message {
@@ -46,13 +48,59 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 None => draw_info(f, &rects[1], game_state),
             }
 
-            draw_score(f, app, &main[1], game_state);
+            let turn = match game_state {
+                GameState::GameInProgress(_, player, _) => Some(*player),
+                GameState::GameOver(..) => None,
+            };
+            draw_score(f, app, &main[1], turn);
+        }
+        AppState::PlayingNumerical(state) => {
+            let turn = match state {
+                NumericalState::GameInProgress(board, player, pos) => {
+                    draw_board(f, number_cells(board), Some(*pos), &main[0]);
+                    Some(*player)
+                }
+                NumericalState::GameOver(winner, board) => {
+                    let result = result_layout(&main[0]);
+                    draw_board(f, number_cells(board), None, &result[0]);
+                    draw_result(f, &result[1], *winner);
+                    None
+                }
+            };
+            let how_to_play = "Press 1-9 to place a number: odd numbers for Player 1, even for Player 2. A line \
+                summing to 15 wins.\nUse the arrow keys to move.";
+            match &app.warning_message {
+                Some(message) => draw_warning(f, &rects[1], message.to_string()),
+                None => draw_variant_info(f, &rects[1], turn.map(|_| how_to_play)),
+            }
+            draw_score(f, app, &main[1], turn);
+        }
+        AppState::PlayingQubic(state) => {
+            let turn = match state {
+                QubicState::GameInProgress(board, player, pos) => {
+                    draw_layers(f, board, Some(*pos), &main[0]);
+                    Some(*player)
+                }
+                QubicState::GameOver(winner, board) => {
+                    let result = result_layout(&main[0]);
+                    draw_layers(f, board, None, &result[0]);
+                    draw_result(f, &result[1], *winner);
+                    None
+                }
+            };
+            let how_to_play = "Press P to place a piece. Four in a row along any line through the cube wins.\nUse \
+                the arrow keys to move the piece, and Page Up/ Page Down to change layers.";
+            match &app.warning_message {
+                Some(message) => draw_warning(f, &rects[1], message.to_string()),
+                None => draw_variant_info(f, &rects[1], turn.map(|_| how_to_play)),
+            }
+            draw_score(f, app, &main[1], turn);
         }
         _ => {}
     }
 }
 
-fn draw_start_menu<B: Backend>(f: &mut Frame<B>, row: usize) {
+fn draw_start_menu<B: Backend>(f: &mut Frame<B>, row: usize, app: &App) {
     let rect = Layout::default()
         .constraints([Constraint::Percentage(100)].as_ref())
         .direction(Direction::Vertical)
@@ -61,6 +109,10 @@ fn draw_start_menu<B: Backend>(f: &mut Frame<B>, row: usize) {
         Row::new([Cell::from("Play against human")]),
         Row::new([Cell::from("Play against random computer")]),
         Row::new([Cell::from("Play against smart computer")]),
+        Row::new([Cell::from("Play against easy computer")]),
+        Row::new([Cell::from("Play against medium computer")]),
+        Row::new([Cell::from("Play against hard computer")]),
+        Row::new([Cell::from(format!("Rules: {} (press Enter to change)", app.rules))]),
     ])
     .block(Block::default().borders(Borders::ALL).title("Start Menu"))
     .highlight_style(Style::default().fg(Color::Yellow))
@@ -96,7 +148,8 @@ fn get_color(player: Player) -> Color {
     }
 }
 
-fn draw_score<B: Backend>(f: &mut Frame<B>, app: &App, rect: &Rect, game_state: &GameState) {
+/// Draws the score and whose turn it is, or that the game is over if no one is to move.
+fn draw_score<B: Backend>(f: &mut Frame<B>, app: &App, rect: &Rect, turn: Option<Player>) {
     let table = Table::new(vec![
         Row::new(vec![Cell::from("Score:".to_string())]),
         Row::new(vec![Cell::from(format!(
@@ -105,8 +158,8 @@ fn draw_score<B: Backend>(f: &mut Frame<B>, app: &App, rect: &Rect, game_state:
         ))])
         .style(Style::default().fg(Color::Yellow))
         .height(2),
-        if let GameState::GameInProgress(_, player, _) = game_state {
-            Row::new(vec![Cell::from(format!("{}'s turn", player,))]).style(Style::default().fg(get_color(*player)))
+        if let Some(player) = turn {
+            Row::new(vec![Cell::from(format!("{}'s turn", player,))]).style(Style::default().fg(get_color(player)))
         } else {
             Row::new(vec![Cell::from("Game Over".to_string())]).style(Style::default().fg(Color::Red))
         },
@@ -117,12 +170,23 @@ fn draw_score<B: Backend>(f: &mut Frame<B>, app: &App, rect: &Rect, game_state:
     f.render_widget(table, *rect)
 }
 
-fn draw_game_over<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Player>, cells: Cells) {
+/// Draws the final board with the cells of the winning `lines` highlighted.
+fn draw_game_over<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Player>, cells: Cells, lines: &[Line]) {
+    let (width, height) = board_size(&cells);
+    let highlight_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::REVERSED);
     let mut rows = cells
         .iter()
-        .map(|item| {
-            let cells = item.iter().map(|c| Cell::from(Span::raw(c.to_text(None))));
-            Row::new(cells).height(rect.height / 4)
+        .enumerate()
+        .map(|(y, item)| {
+            let cells = item.iter().enumerate().map(|(x, c)| {
+                let cell = Cell::from(Span::raw(c.to_text(None)));
+                if lines.iter().flatten().any(|pos| *pos == Position { x, y }) {
+                    cell.style(highlight_style)
+                } else {
+                    cell
+                }
+            });
+            Row::new(cells).height(rect.height / (height + 1))
         })
         .collect::<Vec<_>>();
     let winning_message = match winner {
@@ -130,8 +194,9 @@ fn draw_game_over<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Play
         None => "It's a draw!".to_string(),
     };
     rows.push(Row::new([Cell::from(Span::raw(&winning_message))]));
+    let widths = vec![Constraint::Ratio(1, width); width as usize];
     let t = Table::new(rows)
-        .widths(&[Constraint::Ratio(1, 3); 3])
+        .widths(&widths)
         .block(Block::default().title("Game Over").borders(Borders::ALL))
         .style(
             Style::default()
@@ -164,8 +229,44 @@ fn draw_warning<B: Backend>(f: &mut Frame<B>, rect: &Rect, message: String) {
     f.render_widget(block, *rect);
 }
 
-fn draw_board<B: Backend>(f: &mut Frame<B>, cells: Cells, pos: Position, rect: &Rect) {
+/// A cell that [`draw_board`] knows how to show.
+trait BoardCell {
+    fn text(&self) -> String;
+    fn color(&self) -> Color;
+}
+
+impl BoardCell for GameCell {
+    fn text(&self) -> String {
+        self.to_text(None)
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            GameCell::Empty => Color::Gray,
+            GameCell::Cross => Color::Red,
+            GameCell::Circle => Color::Blue,
+        }
+    }
+}
+
+impl BoardCell for NumberCell {
+    fn text(&self) -> String {
+        self.to_text()
+    }
+
+    /// Numbers take the colour of the player they belong to.
+    fn color(&self) -> Color {
+        match self.number().and_then(numerical::owner) {
+            Some(player) => get_color(player),
+            None => Color::Gray,
+        }
+    }
+}
+
+/// Draws `cells`, highlighting the cell under the cursor at `pos` if there is one.
+fn draw_board<B: Backend, C: BoardCell>(f: &mut Frame<B>, cells: Vec<Vec<C>>, pos: Option<Position>, rect: &Rect) {
     // TODO: Make it look like a tic tac toe board
+    let (width, height) = board_size(&cells);
     let selected_style = Style::default().add_modifier(Modifier::REVERSED);
     let rows = cells.iter().enumerate().map(|(i, item)| {
         // let height = item
@@ -175,33 +276,57 @@ fn draw_board<B: Backend>(f: &mut Frame<B>, cells: Cells, pos: Position, rect: &
         //     .unwrap_or(0)
         //     + 1;
         let cells = item.iter().enumerate().map(|(j, c)| {
-            Cell::from(Span::raw(c.to_text(Some((i, j))))).style(if (i, j) == pos.to_tuple() {
+            Cell::from(Span::raw(c.text())).style(if Some((i, j)) == pos.map(|pos| pos.to_tuple()) {
                 selected_style
             } else {
-                Style::default().fg(match c {
-                    GameCell::Empty => Color::Gray,
-                    GameCell::Cross => Color::Red,
-                    GameCell::Circle => Color::Blue,
-                })
+                Style::default().fg(c.color())
             })
         });
-        Row::new(cells).height(rect.height / 3)
+        Row::new(cells).height(rect.height / height)
     });
+    let widths = vec![Constraint::Ratio(1, width); width as usize];
     let t = Table::new(rows)
         .block(Block::default().borders(Borders::ALL))
-        .widths(&[Constraint::Ratio(1, 3); 3]);
+        .widths(&widths);
     f.render_widget(t, *rect)
 }
 
+/// Draws the layers of a Qubic cube one above the other, top layer first, with the cursor in
+/// the layer it is on if there is one.
+fn draw_layers<B: Backend>(f: &mut Frame<B>, board: &QubicBoard, pos: Option<Position3>, rect: &Rect) {
+    let layers = Layout::default()
+        .constraints(vec![Constraint::Ratio(1, qubic::SIZE as u32); qubic::SIZE])
+        .direction(Direction::Vertical)
+        .split(*rect);
+    for (z, layer) in layers.iter().enumerate() {
+        let cursor = pos.filter(|pos| pos.z == z).map(|pos| pos.flat());
+        draw_board(f, board.layer(z), cursor, layer);
+    }
+}
+
+/// The cells of a numerical board as rows, ready for [`draw_board`].
+fn number_cells(board: &NumericalBoard) -> Vec<Vec<NumberCell>> {
+    board.cells.iter().map(|row| row.to_vec()).collect()
+}
+
+/// Number of columns and rows in `cells`, never less than one so it can be used as a divisor.
+fn board_size<C>(cells: &[Vec<C>]) -> (u32, u16) {
+    let width = cells.first().map_or(0, |row| row.len()).max(1);
+    let height = cells.len().max(1);
+    (width as u32, height as u16)
+}
+
 fn draw_info<B: Backend>(f: &mut Frame<B>, rect: &Rect, state: &GameState) {
     let info = match state {
         GameState::GameInProgress(_, _, _) => {
-            "Game in progress...\nPress M/ Esc to open the Game Menu\nPress P to place a piece, Q to \
-            quit, or R to reset the board.\nUse the arrow keys to move the piece."
+            "Game in progress...\nPress M/ Esc to open the Game Menu\nPress P to place a piece (X or O to pick it in \
+            wild games), U to undo, Y to redo, H for a hint, Q to quit, or R to reset the board.\nUse the arrow keys \
+            to move the piece."
                 .to_string()
         }
         GameState::GameOver(..) => {
-            "Game over!\nPress M/ Esc to open the Game Menu\nPress R to reset the board or Q to quit.".to_string()
+            "Game over!\nPress M/ Esc to open the Game Menu\nPress U to undo, R to reset the board or Q to quit."
+                .to_string()
         }
         // TODO:: Add Menu info
         // GameState::Menu(_) => "Tic Tac Toe Menu\nPress Q to quit, or use the up and down arrow keys to select an item."
@@ -210,3 +335,39 @@ fn draw_info<B: Backend>(f: &mut Frame<B>, rect: &Rect, state: &GameState) {
     let text_block = Paragraph::new(info).block(Block::default().title("Info").borders(Borders::ALL));
     f.render_widget(text_block, *rect);
 }
+
+/// Splits `rect` into room for a finished board and, below it, the result.
+fn result_layout(rect: &Rect) -> Vec<Rect> {
+    Layout::default()
+        .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+        .split(*rect)
+}
+
+fn draw_result<B: Backend>(f: &mut Frame<B>, rect: &Rect, winner: Option<Player>) {
+    let message = match winner {
+        Some(winner) => format!("{} wins!", winner),
+        None => "It's a draw!".to_string(),
+    };
+    let block = Paragraph::new(message)
+        .block(Block::default().title("Game Over").borders(Borders::ALL))
+        .style(
+            Style::default()
+                .fg(winner.map_or(Color::Gray, get_color))
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(block, *rect);
+}
+
+/// Info for the variants that have no undo or hints, with `how_to_play` while the game is in
+/// progress.
+fn draw_variant_info<B: Backend>(f: &mut Frame<B>, rect: &Rect, how_to_play: Option<&str>) {
+    let info = match how_to_play {
+        Some(how_to_play) => format!(
+            "Game in progress...\nPress M/ Esc to open the Game Menu, Q to quit or R to reset the board.\n{}",
+            how_to_play
+        ),
+        None => "Game over!\nPress M/ Esc to open the Game Menu\nPress R to reset the board or Q to quit.".to_string(),
+    };
+    let text_block = Paragraph::new(info).block(Block::default().title("Info").borders(Borders::ALL));
+    f.render_widget(text_block, *rect);
+}